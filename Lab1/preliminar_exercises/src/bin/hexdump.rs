@@ -0,0 +1,48 @@
+use std::io::{self, Write};
+use std::process::ExitCode;
+
+use clap::Parser;
+use preliminar_exercises::file;
+use preliminar_exercises::hexdump::{self, Format, Options};
+
+/// Dump esadecimale di un file, 16 byte per riga con offset e gutter ASCII.
+#[derive(Parser, Debug)]
+struct Args {
+    path: String,
+    /// formato compatibile con `hexdump -C`
+    #[arg(short = 'C')]
+    canonical: bool,
+    /// byte da saltare all'inizio del file
+    #[arg(short, long, default_value_t = 0)]
+    skip: u64,
+    /// numero massimo di byte da mostrare
+    #[arg(short = 'n', long)]
+    length: Option<u64>,
+}
+
+fn main() -> ExitCode {
+    let args = Args::parse();
+    let opts = Options {
+        skip: args.skip,
+        length: args.length,
+        format: if args.canonical { Format::Canonical } else { Format::Plain },
+    };
+
+    let reader = match file::open(&args.path) {
+        Ok(r) => r,
+        Err(e) => {
+            eprintln!("{e}");
+            return ExitCode::FAILURE;
+        }
+    };
+    let mut out = io::BufWriter::new(io::stdout().lock());
+    match hexdump::dump(reader, &mut out, &opts).and_then(|_| out.flush()) {
+        Ok(_) => ExitCode::SUCCESS,
+        // es. `hexdump file | head`
+        Err(e) if e.kind() == io::ErrorKind::BrokenPipe => ExitCode::SUCCESS,
+        Err(e) => {
+            eprintln!("{}: {e}", args.path);
+            ExitCode::FAILURE
+        }
+    }
+}
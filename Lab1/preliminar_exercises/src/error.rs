@@ -0,0 +1,78 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{Duration, SystemTime};
+
+/// Operazione che ha generato l'errore.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Op {
+    Read,
+    Write,
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Op::Read => write!(f, "lettura"),
+            Op::Write => write!(f, "scrittura"),
+        }
+    }
+}
+
+/// Errore su un'operazione su file: conserva il tipo di errore di `std::io`,
+/// il path coinvolto, l'istante in cui è stato rilevato e l'errore originale
+/// (accessibile tramite `Error::source`).
+#[derive(Debug)]
+pub struct FileError {
+    op: Op,
+    kind: io::ErrorKind,
+    path: PathBuf,
+    time: SystemTime,
+    source: io::Error,
+}
+
+impl FileError {
+    pub fn new(op: Op, path: impl AsRef<Path>, source: io::Error) -> Self {
+        Self {
+            op,
+            kind: source.kind(),
+            path: path.as_ref().to_path_buf(),
+            time: SystemTime::now(),
+            source,
+        }
+    }
+
+    pub fn op(&self) -> Op {
+        self.op
+    }
+
+    pub fn kind(&self) -> io::ErrorKind {
+        self.kind
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    pub fn time(&self) -> SystemTime {
+        self.time
+    }
+
+    /// Tempo trascorso da quando l'errore è stato rilevato.
+    pub fn elapsed(&self) -> Duration {
+        self.time.elapsed().unwrap_or_default()
+    }
+}
+
+impl fmt::Display for FileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "errore in {} di {}: {}", self.op, self.path.display(), self.kind)
+    }
+}
+
+impl Error for FileError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        Some(&self.source)
+    }
+}
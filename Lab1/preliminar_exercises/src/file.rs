@@ -0,0 +1,31 @@
+use std::fs::{self, File};
+use std::io::BufReader;
+use std::path::Path;
+
+use crate::error::{FileError, Op};
+
+/// Legge l'intero contenuto di `path` come stringa UTF-8.
+pub fn read_to_string(path: impl AsRef<Path>) -> Result<String, FileError> {
+    let path = path.as_ref();
+    fs::read_to_string(path).map_err(|e| FileError::new(Op::Read, path, e))
+}
+
+/// Legge l'intero contenuto di `path` come sequenza di byte.
+pub fn read(path: impl AsRef<Path>) -> Result<Vec<u8>, FileError> {
+    let path = path.as_ref();
+    fs::read(path).map_err(|e| FileError::new(Op::Read, path, e))
+}
+
+/// Scrive `contents` in `path`, sovrascrivendo il file se esiste già.
+pub fn write(path: impl AsRef<Path>, contents: impl AsRef<[u8]>) -> Result<(), FileError> {
+    let path = path.as_ref();
+    fs::write(path, contents).map_err(|e| FileError::new(Op::Write, path, e))
+}
+
+/// Apre `path` in lettura bufferizzata, per leggere file grandi a pezzi.
+pub fn open(path: impl AsRef<Path>) -> Result<BufReader<File>, FileError> {
+    let path = path.as_ref();
+    File::open(path)
+        .map(BufReader::new)
+        .map_err(|e| FileError::new(Op::Read, path, e))
+}
@@ -0,0 +1,111 @@
+use std::io::{self, BufRead, Read, Write};
+
+/// Numero di byte mostrati per ogni riga.
+pub const ROW: usize = 16;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Format {
+    /// `00000000: 68 65 6c 6c 6f  hello`
+    #[default]
+    Plain,
+    /// Compatibile con `hexdump -C`: gruppi da 8 byte, gutter tra `|`
+    /// e righe identiche consecutive compresse in `*`.
+    Canonical,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct Options {
+    /// Byte da saltare all'inizio dell'input.
+    pub skip: u64,
+    /// Numero massimo di byte da mostrare (`None` = fino alla fine).
+    pub length: Option<u64>,
+    pub format: Format,
+}
+
+/// Stampa su `out` il dump esadecimale di `input`, leggendolo una riga alla
+/// volta: la memoria usata non dipende dalla dimensione dell'input.
+pub fn dump<R: BufRead, W: Write>(mut input: R, out: &mut W, opts: &Options) -> io::Result<()> {
+    let skipped = io::copy(&mut input.by_ref().take(opts.skip), &mut io::sink())?;
+    let mut input = input.take(opts.length.unwrap_or(u64::MAX));
+
+    let mut offset = skipped;
+    let mut row = [0u8; ROW];
+    let mut prev: Option<[u8; ROW]> = None;
+    let mut squeezing = false;
+
+    loop {
+        let n = fill(&mut input, &mut row)?;
+        if n == 0 {
+            break;
+        }
+        match opts.format {
+            Format::Plain => write_plain(out, offset, &row[..n])?,
+            Format::Canonical => {
+                if n == ROW && prev == Some(row) {
+                    if !squeezing {
+                        writeln!(out, "*")?;
+                        squeezing = true;
+                    }
+                } else {
+                    write_canonical(out, offset, &row[..n])?;
+                    squeezing = false;
+                }
+                prev = if n == ROW { Some(row) } else { None };
+            }
+        }
+        offset += n as u64;
+    }
+
+    if opts.format == Format::Canonical && offset > skipped {
+        writeln!(out, "{:08x}", offset)?;
+    }
+    Ok(())
+}
+
+/// Riempie `buf` il più possibile; restituisce meno di `buf.len()` byte
+/// solo a fine input.
+fn fill<R: Read>(input: &mut R, buf: &mut [u8]) -> io::Result<usize> {
+    let mut n = 0;
+    while n < buf.len() {
+        match input.read(&mut buf[n..]) {
+            Ok(0) => break,
+            Ok(k) => n += k,
+            Err(e) if e.kind() == io::ErrorKind::Interrupted => {}
+            Err(e) => return Err(e),
+        }
+    }
+    Ok(n)
+}
+
+fn write_plain<W: Write>(out: &mut W, offset: u64, bytes: &[u8]) -> io::Result<()> {
+    write!(out, "{:08x}:", offset)?;
+    for i in 0..ROW {
+        match bytes.get(i) {
+            Some(b) => write!(out, " {:02x}", b)?,
+            None => write!(out, "   ")?,
+        }
+    }
+    writeln!(out, "  {}", gutter(bytes))
+}
+
+fn write_canonical<W: Write>(out: &mut W, offset: u64, bytes: &[u8]) -> io::Result<()> {
+    write!(out, "{:08x}  ", offset)?;
+    for i in 0..ROW {
+        match bytes.get(i) {
+            Some(b) => write!(out, "{:02x} ", b)?,
+            None => write!(out, "   ")?,
+        }
+        if i == ROW / 2 - 1 {
+            write!(out, " ")?;
+        }
+    }
+    writeln!(out, " |{}|", gutter(bytes))
+}
+
+/// Caratteri ASCII stampabili così come sono, tutto il resto come `.`
+fn gutter(bytes: &[u8]) -> String {
+    bytes
+        .iter()
+        .map(|&b| if b.is_ascii_graphic() || b == b' ' { b as char } else { '.' })
+        .collect()
+}
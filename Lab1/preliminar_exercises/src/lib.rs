@@ -0,0 +1,9 @@
+pub mod error;
+pub mod file;
+pub mod hexdump;
+pub mod node;
+pub mod store;
+
+pub use error::{FileError, Op};
+pub use node::{Node, NodeBuilder, NodeError};
+pub use store::{NodeStore, StoreError};
@@ -0,0 +1,138 @@
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+/// Piccolo record di inventario, serializzato come `name:<nome> size:<n> count:<n>`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Node {
+    name: String,
+    size: u32,
+    count: u32,
+}
+
+impl Node {
+    /// Nodo con `size` e `count` a zero.
+    pub fn new(name: impl Into<String>) -> Result<Self, NodeError> {
+        NodeBuilder::new(name).build()
+    }
+
+    pub fn builder(name: impl Into<String>) -> NodeBuilder {
+        NodeBuilder::new(name)
+    }
+
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    pub fn size(&self) -> u32 {
+        self.size
+    }
+
+    pub fn count(&self) -> u32 {
+        self.count
+    }
+
+    pub fn grow(&mut self) {
+        self.size = self.size.saturating_add(1);
+    }
+
+    pub fn inc(&mut self) {
+        self.count = self.count.saturating_add(1);
+    }
+}
+
+impl fmt::Display for Node {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "name:{} size:{} count:{}", self.name, self.size, self.count)
+    }
+}
+
+impl FromStr for Node {
+    type Err = NodeError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let mut tokens = s.split_whitespace();
+        let name = field(tokens.next(), "name")?;
+        let size = number(field(tokens.next(), "size")?, "size")?;
+        let count = number(field(tokens.next(), "count")?, "count")?;
+        if let Some(t) = tokens.next() {
+            return Err(NodeError::UnexpectedToken(t.to_string()));
+        }
+        NodeBuilder::new(name).size(size).count(count).build()
+    }
+}
+
+fn field<'a>(token: Option<&'a str>, key: &'static str) -> Result<&'a str, NodeError> {
+    token
+        .and_then(|t| t.strip_prefix(key))
+        .and_then(|t| t.strip_prefix(':'))
+        .ok_or(NodeError::MissingField(key))
+}
+
+fn number(value: &str, key: &'static str) -> Result<u32, NodeError> {
+    value.parse().map_err(|_| NodeError::InvalidField {
+        field: key,
+        value: value.to_string(),
+    })
+}
+
+/// Costruisce un `Node` validandone il nome: non vuoto e senza spazi, così
+/// che `Display` e `FromStr` siano l'uno l'inverso dell'altro.
+#[derive(Debug, Clone)]
+pub struct NodeBuilder {
+    name: String,
+    size: u32,
+    count: u32,
+}
+
+impl NodeBuilder {
+    pub fn new(name: impl Into<String>) -> Self {
+        Self { name: name.into(), size: 0, count: 0 }
+    }
+
+    pub fn size(mut self, size: u32) -> Self {
+        self.size = size;
+        self
+    }
+
+    pub fn count(mut self, count: u32) -> Self {
+        self.count = count;
+        self
+    }
+
+    pub fn build(self) -> Result<Node, NodeError> {
+        if self.name.is_empty() {
+            return Err(NodeError::EmptyName);
+        }
+        if self.name.chars().any(char::is_whitespace) {
+            return Err(NodeError::InvalidName(self.name));
+        }
+        Ok(Node { name: self.name, size: self.size, count: self.count })
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum NodeError {
+    EmptyName,
+    /// Il nome contiene spazi.
+    InvalidName(String),
+    MissingField(&'static str),
+    InvalidField { field: &'static str, value: String },
+    UnexpectedToken(String),
+}
+
+impl fmt::Display for NodeError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            NodeError::EmptyName => write!(f, "nome vuoto"),
+            NodeError::InvalidName(name) => write!(f, "nome non valido: {name:?}"),
+            NodeError::MissingField(key) => write!(f, "campo `{key}` mancante"),
+            NodeError::InvalidField { field, value } => {
+                write!(f, "valore non valido per `{field}`: {value:?}")
+            }
+            NodeError::UnexpectedToken(t) => write!(f, "testo inatteso: {t:?}"),
+        }
+    }
+}
+
+impl Error for NodeError {}
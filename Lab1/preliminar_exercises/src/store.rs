@@ -0,0 +1,71 @@
+use std::error::Error;
+use std::fmt;
+use std::path::{Path, PathBuf};
+
+use crate::error::FileError;
+use crate::file;
+use crate::node::{Node, NodeError};
+
+/// Salva una lista di `Node` su file, uno per riga nel formato di `Display`.
+#[derive(Debug, Clone)]
+pub struct NodeStore {
+    path: PathBuf,
+}
+
+impl NodeStore {
+    pub fn new(path: impl AsRef<Path>) -> Self {
+        Self { path: path.as_ref().to_path_buf() }
+    }
+
+    pub fn path(&self) -> &Path {
+        &self.path
+    }
+
+    /// Le righe vuote vengono ignorate.
+    pub fn load(&self) -> Result<Vec<Node>, StoreError> {
+        let content = file::read_to_string(&self.path)?;
+        content
+            .lines()
+            .enumerate()
+            .filter(|(_, l)| !l.trim().is_empty())
+            .map(|(i, l)| l.parse().map_err(|source| StoreError::Parse { line: i + 1, source }))
+            .collect()
+    }
+
+    pub fn save(&self, nodes: &[Node]) -> Result<(), StoreError> {
+        let content: String = nodes.iter().map(|n| format!("{n}\n")).collect();
+        file::write(&self.path, content)?;
+        Ok(())
+    }
+}
+
+#[derive(Debug)]
+pub enum StoreError {
+    File(FileError),
+    /// `line` parte da 1.
+    Parse { line: usize, source: NodeError },
+}
+
+impl fmt::Display for StoreError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            StoreError::File(e) => write!(f, "{e}"),
+            StoreError::Parse { line, source } => write!(f, "riga {line}: {source}"),
+        }
+    }
+}
+
+impl Error for StoreError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            StoreError::File(e) => Some(e),
+            StoreError::Parse { source, .. } => Some(source),
+        }
+    }
+}
+
+impl From<FileError> for StoreError {
+    fn from(e: FileError) -> Self {
+        StoreError::File(e)
+    }
+}
@@ -0,0 +1,45 @@
+use std::error::Error;
+use std::io::ErrorKind;
+use std::path::PathBuf;
+
+use preliminar_exercises::{file, Op};
+
+fn tmp_path(name: &str) -> PathBuf {
+    std::env::temp_dir().join(format!("preliminar_exercises_{}_{}", std::process::id(), name))
+}
+
+#[test]
+fn write_then_read_to_string() {
+    let path = tmp_path("write_then_read.txt");
+    file::write(&path, "ciao".repeat(3)).unwrap();
+    assert_eq!(file::read_to_string(&path).unwrap(), "ciaociaociao");
+    assert_eq!(file::read(&path).unwrap(), b"ciaociaociao");
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn write_uses_the_given_path() {
+    let path = tmp_path("given_path.txt");
+    file::write(&path, "x").unwrap();
+    assert!(path.exists());
+    std::fs::remove_file(path).unwrap();
+}
+
+#[test]
+fn read_missing_file_keeps_kind_path_and_source() {
+    let path = tmp_path("missing.txt");
+    let err = file::read_to_string(&path).unwrap_err();
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+    assert_eq!(err.op(), Op::Read);
+    assert_eq!(err.path(), path.as_path());
+    assert!(err.source().is_some());
+    assert!(err.to_string().contains("lettura"));
+}
+
+#[test]
+fn write_in_missing_dir_is_write_error() {
+    let path = tmp_path("no_such_dir").join("a.txt");
+    let err = file::write(&path, "x").unwrap_err();
+    assert_eq!(err.op(), Op::Write);
+    assert_eq!(err.kind(), ErrorKind::NotFound);
+}
@@ -0,0 +1,66 @@
+use preliminar_exercises::hexdump::{dump, Format, Options};
+
+fn run(input: &[u8], opts: Options) -> String {
+    let mut out = Vec::new();
+    dump(input, &mut out, &opts).unwrap();
+    String::from_utf8(out).unwrap()
+}
+
+fn canonical() -> Options {
+    Options { format: Format::Canonical, ..Default::default() }
+}
+
+#[test]
+fn canonical_partial_row() {
+    let out = run(b"hello\n", canonical());
+    assert_eq!(
+        out,
+        "00000000  68 65 6c 6c 6f 0a                                 |hello.|\n00000006\n"
+    );
+}
+
+#[test]
+fn canonical_full_rows() {
+    let out = run(b"0123456789abcdefXYZ", canonical());
+    assert_eq!(
+        out,
+        "00000000  30 31 32 33 34 35 36 37  38 39 61 62 63 64 65 66  |0123456789abcdef|\n\
+         00000010  58 59 5a                                          |XYZ|\n\
+         00000013\n"
+    );
+}
+
+#[test]
+fn canonical_squeezes_repeated_rows() {
+    let out = run(&[0u8; 64], canonical());
+    assert_eq!(
+        out,
+        "00000000  00 00 00 00 00 00 00 00  00 00 00 00 00 00 00 00  |................|\n\
+         *\n\
+         00000040\n"
+    );
+}
+
+#[test]
+fn canonical_empty_input_prints_nothing() {
+    assert_eq!(run(b"", canonical()), "");
+}
+
+#[test]
+fn skip_and_length() {
+    let opts = Options { skip: 2, length: Some(3), format: Format::Canonical };
+    let out = run(b"abcdefgh", opts);
+    assert_eq!(
+        out,
+        "00000002  63 64 65                                          |cde|\n00000005\n"
+    );
+}
+
+#[test]
+fn plain_multibyte_utf8_stays_aligned() {
+    let out = run("così".as_bytes(), Options::default());
+    assert_eq!(
+        out,
+        format!("00000000: 63 6f 73 c3 ac{}  cos..\n", "   ".repeat(11))
+    );
+}
@@ -0,0 +1,58 @@
+use preliminar_exercises::{Node, NodeError, NodeStore, StoreError};
+
+#[test]
+fn builder_sets_fields() {
+    let node = Node::builder("nodo").size(10).count(5).build().unwrap();
+    assert_eq!(node.name(), "nodo");
+    assert_eq!(node.size(), 10);
+    assert_eq!(node.count(), 5);
+}
+
+#[test]
+fn builder_rejects_invalid_names() {
+    assert_eq!(Node::new(""), Err(NodeError::EmptyName));
+    assert_eq!(Node::new("due nomi"), Err(NodeError::InvalidName("due nomi".to_string())));
+}
+
+#[test]
+fn display_from_str_round_trip() {
+    let node = Node::builder("nodo").size(10).count(5).build().unwrap();
+    assert_eq!(node.to_string(), "name:nodo size:10 count:5");
+    assert_eq!(node.to_string().parse::<Node>().unwrap(), node);
+}
+
+#[test]
+fn from_str_errors() {
+    assert_eq!("name:a size:x count:1".parse::<Node>(), Err(NodeError::InvalidField { field: "size", value: "x".to_string() }));
+    assert_eq!("name:a count:1".parse::<Node>(), Err(NodeError::MissingField("size")));
+    assert_eq!("name:a size:1 count:1 extra".parse::<Node>(), Err(NodeError::UnexpectedToken("extra".to_string())));
+}
+
+#[test]
+fn grow_and_inc_saturate() {
+    let mut node = Node::builder("nodo").size(u32::MAX).count(u32::MAX - 1).build().unwrap();
+    node.grow();
+    node.inc();
+    node.inc();
+    assert_eq!(node.size(), u32::MAX);
+    assert_eq!(node.count(), u32::MAX);
+}
+
+#[test]
+fn store_save_then_load() {
+    let path = std::env::temp_dir().join(format!("preliminar_exercises_{}_store.txt", std::process::id()));
+    let store = NodeStore::new(&path);
+    let nodes = vec![
+        Node::builder("a").size(1).count(2).build().unwrap(),
+        Node::builder("b").size(3).count(4).build().unwrap(),
+    ];
+    store.save(&nodes).unwrap();
+    assert_eq!(store.load().unwrap(), nodes);
+
+    std::fs::write(&path, "name:a size:1 count:2\n\nname:b size:-3 count:4\n").unwrap();
+    match store.load() {
+        Err(StoreError::Parse { line, .. }) => assert_eq!(line, 3),
+        other => panic!("atteso errore di parsing, ottenuto {other:?}"),
+    }
+    std::fs::remove_file(path).unwrap();
+}
@@ -0,0 +1,106 @@
+// cargo bench --bench search
+//
+// Throughput of the DFA matcher on multi-megabase random sequences, compared with the
+// old strategy of calling find on the rest of the sequence after every match.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use esercizio1::dna::{find_approx, find_approx_edits, par_subsequences, MatchKind, MultiPatternSearcher, Pattern};
+
+fn random_dna(len: usize, mut seed: u64) -> String {
+    (0..len)
+        .map(|_| {
+            seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+            b"ACGT"[((seed >> 33) % 4) as usize] as char
+        })
+        .collect()
+}
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let n = black_box(f());
+    (start.elapsed(), n)
+}
+
+fn mb_per_s(len: usize, d: Duration) -> f64 {
+    len as f64 / 1e6 / d.as_secs_f64()
+}
+
+// what subsequences1 did before: a new search on the suffix after each match
+fn restart_per_match(pat: &Pattern, s: &str) -> usize {
+    let mut count = 0;
+    let mut last = 0;
+    while let Some((i, m)) = pat.find(&s[last..]) {
+        count += 1;
+        last += i + m.len();
+    }
+    count
+}
+
+fn main() {
+    let patterns = ["A1-1,C2-4", "[AG]2-5,T1-3,N2,C", "A3-,C|G4-", "(A,C)2-4|T5-"];
+
+    println!("{:<22} {:>8} {:>10} {:>12} {:>12}", "pattern", "Mb", "matches", "non-overlap", "overlap");
+    for p in patterns {
+        let pat = Pattern::parse(p).unwrap();
+        for len in [1 << 20, 4 << 20, 16 << 20] {
+            let s = random_dna(len, len as u64);
+            let (d, n) = time(|| pat.find_iter(&s).count());
+            let (d2, _) = time(|| pat.find_overlapping_iter(&s).count());
+            println!(
+                "{:<22} {:>8} {:>10} {:>7.1} MB/s {:>7.1} MB/s",
+                p,
+                len >> 20,
+                n,
+                mb_per_s(len, d),
+                mb_per_s(len, d2)
+            );
+        }
+    }
+
+    println!("\nrestart after each match (old subsequences1), pattern A1-1,C2-4");
+    let pat = Pattern::parse("A1-1,C2-4").unwrap();
+    for len in [16 << 10, 32 << 10, 64 << 10] {
+        let s = random_dna(len, 7);
+        let (old, n) = time(|| restart_per_match(&pat, &s));
+        let (new, n2) = time(|| pat.find_iter(&s).count());
+        assert_eq!(n, n2);
+        println!("{:>6} KiB: restart {:>10.2?}  find_iter {:>10.2?}", len >> 10, old, new);
+    }
+
+    println!("\n300 random 8-mers on 4 Mb");
+    let s = random_dna(4 << 20, 11);
+    let motifs: Vec<Pattern> = (0..300)
+        .map(|k| Pattern::parse(&random_dna(8, 1000 + k)).unwrap())
+        .collect();
+    let (build, searcher) = {
+        let start = Instant::now();
+        let searcher = MultiPatternSearcher::new(motifs.clone()).unwrap();
+        (start.elapsed(), searcher)
+    };
+    let (multi, n) = time(|| searcher.find_iter(&s, MatchKind::PerPattern).count());
+    let (single, n2) = time(|| motifs.iter().map(|p| p.find_iter(&s).count()).sum());
+    assert_eq!(n, n2);
+    println!("build {:.2?}, one pass {:.2?}, one search per motif {:.2?} ({} matches)", build, multi, single, n);
+
+    println!("\npar_subsequences on 16 Mb, pattern [AG]2-5,T1-3,N2,C");
+    let s = random_dna(16 << 20, 5);
+    let pat = Pattern::parse("[AG]2-5,T1-3,N2,C").unwrap();
+    let (seq, expected) = time(|| pat.find_iter(&s).count());
+    println!("sequential {:>10.2?}", seq);
+    for threads in [1, 2, 4, 8] {
+        let (d, n) = time(|| par_subsequences(&s, &pat, threads).len());
+        assert_eq!(n, expected);
+        println!("{:>2} threads {:>10.2?}", threads, d);
+    }
+
+    println!("\napproximate search of a 20-base primer on 4 Mb");
+    let s = random_dna(4 << 20, 6);
+    let pat = Pattern::parse("ACGTTGCANNGTCAGGTCAA").unwrap();
+    for k in [0, 1, 2, 3] {
+        let (h, nh) = time(|| find_approx(&s, &pat, k).unwrap().count());
+        let (e, ne) = time(|| find_approx_edits(&s, &pat, k).unwrap().count());
+        println!("k = {}: mismatches {:>10.2?} ({:>5} hits), edits {:>10.2?} ({:>5} hits)", k, h, nh, e, ne);
+    }
+}
@@ -0,0 +1,467 @@
+// Approximate search of a pattern, with up to k mismatches (Hamming distance) or k edits
+// (Levenshtein distance).
+//
+// The pattern is expanded into all the sequences of base sets it may match (one for a
+// plain primer, one per count for "A2-4", ...), each of at most 64 bases, so that each
+// one fits a machine word: mismatches are counted with the bit-parallel shift-and of
+// Wu and Manber, edits with Myers' bit-vector algorithm. Both read each byte of the
+// sequence once per variant.
+
+use std::cmp::Reverse;
+use std::collections::VecDeque;
+use std::error::Error;
+use std::fmt;
+
+use super::automaton::{symbol, SYMBOLS};
+use super::pattern::{BaseSet, Expr, Pattern};
+
+/// Longest sequence the bit-parallel algorithms can handle.
+pub const MAX_LEN: usize = 64;
+/// Upper bound to the number of sequences a pattern may expand to.
+const MAX_VARIANTS: usize = 1024;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ApproxError {
+    /// The pattern has an open range like "A2-".
+    OpenRange,
+    /// The pattern may match more than `MAX_LEN` bases.
+    TooLong,
+    /// The pattern expands to too many sequences.
+    TooManyVariants,
+}
+
+impl fmt::Display for ApproxError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ApproxError::OpenRange => write!(f, "approximate search of a pattern with an open range"),
+            ApproxError::TooLong => write!(f, "approximate search of a pattern longer than {MAX_LEN}"),
+            ApproxError::TooManyVariants => write!(f, "pattern with too many alternatives"),
+        }
+    }
+}
+
+impl Error for ApproxError {}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Distance {
+    Hamming,
+    Edit,
+}
+
+/// Hits of `pat` in `s` with at most `max_mismatches` substitutions, as
+/// `(position, matched slice, mismatches)`.
+pub fn find_approx<'s>(s: &'s str, pat: &Pattern, max_mismatches: usize) -> Result<ApproxMatches<'s>, ApproxError> {
+    ApproxMatches::new(s, pat, max_mismatches, Distance::Hamming)
+}
+
+/// Hits of `pat` in `s` with at most `max_edits` substitutions, insertions or deletions,
+/// as `(position, matched slice, edits)`.
+pub fn find_approx_edits<'s>(s: &'s str, pat: &Pattern, max_edits: usize) -> Result<ApproxMatches<'s>, ApproxError> {
+    ApproxMatches::new(s, pat, max_edits, Distance::Edit)
+}
+
+/// All the sequences of base sets `expr` may match.
+fn expand(expr: &Expr) -> Result<Vec<Vec<BaseSet>>, ApproxError> {
+    let product = |a: Vec<Vec<BaseSet>>, b: &[Vec<BaseSet>]| -> Result<Vec<Vec<BaseSet>>, ApproxError> {
+        if a.len().saturating_mul(b.len()) > MAX_VARIANTS {
+            return Err(ApproxError::TooManyVariants);
+        }
+        let mut out = Vec::with_capacity(a.len() * b.len());
+        for x in &a {
+            for y in b {
+                if x.len() + y.len() > MAX_LEN {
+                    return Err(ApproxError::TooLong);
+                }
+                out.push([x.as_slice(), y.as_slice()].concat());
+            }
+        }
+        Ok(out)
+    };
+    let mut out = match expr {
+        Expr::Set(set) => vec![vec![*set]],
+        Expr::Concat(items) => {
+            let mut acc = vec![vec![]];
+            for e in items {
+                acc = product(acc, &expand(e)?)?;
+            }
+            acc
+        }
+        Expr::Alt(alts) => {
+            let mut acc = Vec::new();
+            for e in alts {
+                acc.extend(expand(e)?);
+                if acc.len() > MAX_VARIANTS {
+                    return Err(ApproxError::TooManyVariants);
+                }
+            }
+            acc
+        }
+        Expr::Repeat { expr, min, max } => {
+            let max = max.ok_or(ApproxError::OpenRange)?;
+            if max as usize > MAX_LEN {
+                return Err(ApproxError::TooLong);
+            }
+            let inner = expand(expr)?;
+            let mut power = vec![vec![]];
+            for _ in 0..*min {
+                power = product(power, &inner)?;
+            }
+            let mut acc = power.clone();
+            for _ in *min..max {
+                power = product(power, &inner)?;
+                acc.extend(power.iter().cloned());
+                if acc.len() > MAX_VARIANTS {
+                    return Err(ApproxError::TooManyVariants);
+                }
+            }
+            acc
+        }
+    };
+    out.retain(|v| !v.is_empty());
+    out.sort_by_key(|v| v.iter().map(|s| s.bits()).collect::<Vec<_>>());
+    out.dedup();
+    Ok(out)
+}
+
+/// One of the sequences of a pattern, with the state of its bit-parallel search.
+struct Variant {
+    classes: Vec<BaseSet>,
+    /// Bit j set in `peq[sym]` if the j-th base set matches the symbol.
+    peq: [u64; SYMBOLS],
+    high: u64,
+    mask: u64,
+    /// Hamming: `r[d]` has bit j set if the first j+1 bases match with d mismatches.
+    r: Vec<u64>,
+    /// Edit: Myers' vertical deltas and the distance at the last row.
+    pv: u64,
+    mv: u64,
+    score: usize,
+}
+
+impl Variant {
+    fn new(classes: Vec<BaseSet>, k: usize) -> Variant {
+        let m = classes.len();
+        let mut peq = [0u64; SYMBOLS];
+        for (sym, p) in peq.iter_mut().enumerate() {
+            for (j, c) in classes.iter().enumerate() {
+                if c.matches_symbol(sym as u8) {
+                    *p |= 1 << j;
+                }
+            }
+        }
+        let mask = if m == 64 { !0 } else { (1u64 << m) - 1 };
+        Variant { classes, peq, high: 1 << (m - 1), mask, r: vec![0; k + 1], pv: mask, mv: 0, score: m }
+    }
+
+    /// Reads one byte, returns the distance of the best match ending after it.
+    fn step(&mut self, sym: usize, dist: Distance) -> usize {
+        let eq = self.peq[sym];
+        match dist {
+            Distance::Hamming => {
+                let mut prev_old = 0;
+                for d in 0..self.r.len() {
+                    let old = self.r[d];
+                    let mut next = ((old << 1) | 1) & eq;
+                    if d > 0 {
+                        next |= (prev_old << 1) | 1;
+                    }
+                    self.r[d] = next & self.mask;
+                    prev_old = old;
+                }
+                self.r.iter().position(|r| r & self.high != 0).unwrap_or(usize::MAX)
+            }
+            Distance::Edit => {
+                let (pv, mv) = (self.pv, self.mv);
+                let xv = eq | mv;
+                let xh = (((eq & pv).wrapping_add(pv)) ^ pv) | eq;
+                let mut ph = mv | !(xh | pv);
+                let mut mh = pv & xh;
+                if ph & self.high != 0 {
+                    self.score += 1;
+                } else if mh & self.high != 0 {
+                    self.score -= 1;
+                }
+                // the start of the match is free: the first row stays 0
+                ph <<= 1;
+                mh <<= 1;
+                self.pv = (mh | !(xv | ph)) & self.mask;
+                self.mv = ph & xv & self.mask;
+                self.score
+            }
+        }
+    }
+
+    /// Start and edits of the best match ending at `end` and starting at or after
+    /// `min_start`, preferring the longest among the best ones.
+    fn edit_start(&self, bytes: &[u8], end: usize, min_start: usize, k: usize) -> Option<(usize, usize)> {
+        let m = self.classes.len();
+        let width = (m + k).min(end - min_start);
+        // prev[j]: edits between the last i bases of the variant and the j bytes before end
+        let mut prev: Vec<usize> = (0..=width).collect();
+        let mut cur = vec![0; width + 1];
+        for i in 1..=m {
+            let class = self.classes[m - i];
+            cur[0] = i;
+            for j in 1..=width {
+                let cost = usize::from(!class.matches(bytes[end - j]));
+                cur[j] = (prev[j - 1] + cost).min(prev[j] + 1).min(cur[j - 1] + 1);
+            }
+            std::mem::swap(&mut prev, &mut cur);
+        }
+        let (j, d) = prev.iter().enumerate().skip(1).min_by_key(|&(j, d)| (*d, usize::MAX - j))?;
+        (*d <= k).then(|| (end - j, *d))
+    }
+}
+
+/// Iterator over the approximate hits of a pattern, `(position, matched slice, distance)`.
+///
+/// Each hit is the best one (lowest distance, then leftmost start, then longest) among
+/// those ending within a pattern length of the first one found; the search then goes on
+/// after its end, so hits never overlap.
+pub struct ApproxMatches<'s> {
+    s: &'s str,
+    k: usize,
+    dist: Distance,
+    variants: Vec<Variant>,
+    window: usize,
+    /// Next byte to read.
+    fed: usize,
+    /// Ends already read, and whether some variant has a hit ending there.
+    ahead: VecDeque<(usize, bool)>,
+    min_start: usize,
+}
+
+impl<'s> ApproxMatches<'s> {
+    fn new(s: &'s str, pat: &Pattern, k: usize, dist: Distance) -> Result<Self, ApproxError> {
+        let expanded = expand(pat.expr())?;
+        let window = expanded.iter().map(|v| v.len()).max().unwrap_or(1);
+        // a budget of the pattern length already matches everywhere
+        let k = k.min(window);
+        let variants: Vec<Variant> = expanded.into_iter().map(|v| Variant::new(v, k)).collect();
+        Ok(ApproxMatches { s, k, dist, variants, window, fed: 0, ahead: VecDeque::new(), min_start: 0 })
+    }
+
+    /// Reads the next byte and queues its end, false at the end of the sequence.
+    fn feed(&mut self) -> bool {
+        let Some(&b) = self.s.as_bytes().get(self.fed) else {
+            return false;
+        };
+        let sym = symbol(b);
+        let (dist, k) = (self.dist, self.k);
+        // every variant must read the byte: no short-circuit
+        let mut hit = false;
+        for v in &mut self.variants {
+            hit |= v.step(sym, dist) <= k;
+        }
+        self.fed += 1;
+        self.ahead.push_back((self.fed, hit));
+        true
+    }
+
+    /// Start and distance of the best hit ending at `end` that does not overlap the
+    /// previous one, among all the variants.
+    fn start_of(&self, end: usize) -> Option<(usize, usize)> {
+        let bytes = self.s.as_bytes();
+        self.variants
+            .iter()
+            .filter_map(|v| match self.dist {
+                Distance::Hamming => {
+                    let start = end.checked_sub(v.classes.len()).filter(|&st| st >= self.min_start)?;
+                    let d = v.classes.iter().zip(&bytes[start..end]).filter(|(c, b)| !c.matches(**b)).count();
+                    (d <= self.k).then_some((start, d))
+                }
+                Distance::Edit => v.edit_start(bytes, end, self.min_start, self.k),
+            })
+            .min_by_key(|&(start, d)| (d, start))
+    }
+}
+
+impl<'s> Iterator for ApproxMatches<'s> {
+    type Item = (usize, &'s str, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if self.ahead.is_empty() && !self.feed() {
+                return None;
+            }
+            let first = match self.ahead.front()? {
+                &(end, true) => end,
+                _ => {
+                    self.ahead.pop_front();
+                    continue;
+                }
+            };
+            // a better hit may end a little further on: lowest distance, then leftmost
+            // start, then longest
+            let mut best: Option<(usize, usize, usize)> = None;
+            let mut i = 0;
+            loop {
+                if i == self.ahead.len() && !self.feed() {
+                    break;
+                }
+                let (end, hit) = self.ahead[i];
+                if end >= first + self.window {
+                    break;
+                }
+                i += 1;
+                if !hit {
+                    continue;
+                }
+                let Some((start, dist)) = self.start_of(end) else {
+                    continue;
+                };
+                if !self.s.is_char_boundary(start) || !self.s.is_char_boundary(end) {
+                    continue;
+                }
+                if best.is_none_or(|(s, e, d)| (dist, start, Reverse(end)) < (d, s, Reverse(e))) {
+                    best = Some((start, end, dist));
+                }
+            }
+            let Some((start, end, dist)) = best else {
+                self.ahead.pop_front();
+                continue;
+            };
+            while self.ahead.front().is_some_and(|&(e, _)| e <= end) {
+                self.ahead.pop_front();
+            }
+            self.min_start = end;
+            return Some((start, &self.s[start..end], dist));
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn approx(s: &str, p: &str, k: usize) -> Vec<(usize, String, usize)> {
+        let pat = Pattern::parse(p).unwrap();
+        find_approx(s, &pat, k).unwrap().map(|(i, m, d)| (i, m.to_string(), d)).collect()
+    }
+
+    fn edits(s: &str, p: &str, k: usize) -> Vec<(usize, String, usize)> {
+        let pat = Pattern::parse(p).unwrap();
+        find_approx_edits(s, &pat, k).unwrap().map(|(i, m, d)| (i, m.to_string(), d)).collect()
+    }
+
+    fn hits(v: &[(usize, &str, usize)]) -> Vec<(usize, String, usize)> {
+        v.iter().map(|&(i, m, d)| (i, m.to_string(), d)).collect()
+    }
+
+    #[test]
+    fn exact_is_the_same_as_find_iter() {
+        let s = "AACCGGTAACCTTGACCC";
+        let pat = Pattern::parse("A1-1,C2-4").unwrap();
+        let exact: Vec<_> = pat.find_iter(s).map(|(i, m)| (i, m.to_string(), 0)).collect();
+        assert_eq!(approx(s, "A1-1,C2-4", 0), exact);
+        assert_eq!(edits(s, "A1-1,C2-4", 0), exact);
+    }
+
+    #[test]
+    fn mismatches() {
+        let s = "TTTGATTACATTTGACTACATTT";
+        assert_eq!(approx(s, "GATTACA", 0), hits(&[(3, "GATTACA", 0)]));
+        assert_eq!(approx(s, "GATTACA", 1), hits(&[(3, "GATTACA", 0), (13, "GACTACA", 1)]));
+        assert_eq!(approx("GCTTACT", "GATTACA", 1), vec![]);
+        assert_eq!(approx("GCTTACT", "GATTACA", 2), hits(&[(0, "GCTTACT", 2)]));
+    }
+
+    #[test]
+    fn mismatches_with_ambiguity_codes() {
+        assert_eq!(approx("TTGGTTACATT", "GRTTACA", 0), hits(&[(2, "GGTTACA", 0)]));
+        assert_eq!(approx("TTGCTTACATT", "GRTTACA", 1), hits(&[(2, "GCTTACA", 1)]));
+    }
+
+    #[test]
+    fn edits_insertions_and_deletions() {
+        // one base deleted and one inserted
+        assert_eq!(edits("TTTGATACATTT", "GATTACA", 1), hits(&[(3, "GATACA", 1)]));
+        assert_eq!(edits("TTTGATTTACATTT", "GATTACA", 1), hits(&[(3, "GATTTACA", 1)]));
+        assert_eq!(edits("TTTGATTTACATTT", "GATTACA", 0), vec![]);
+        // a substitution is one edit as well
+        assert_eq!(edits("CCGACTACACC", "GATTACA", 1), hits(&[(2, "GACTACA", 1)]));
+    }
+
+    #[test]
+    fn best_hit_in_the_neighbourhood() {
+        // ACGTACGA has a mismatch, ACGTACGT right after is exact
+        assert_eq!(approx("ACGTACGTACGT", "ACGTACGT", 1), hits(&[(0, "ACGTACGT", 0)]));
+        assert_eq!(approx("CCGTACGTACGT", "ACGTACGT", 1), hits(&[(4, "ACGTACGT", 0)]));
+    }
+
+    #[test]
+    fn variants_of_ranges() {
+        assert_eq!(approx("TTACCGT", "A,C2-3,G", 0), hits(&[(2, "ACCG", 0)]));
+        assert_eq!(approx("TTACTGT", "A,C2-3,G", 1), hits(&[(2, "ACTG", 1)]));
+    }
+
+    fn random_dna(len: usize, mut seed: u64) -> String {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                b"ACGT"[((seed >> 33) % 4) as usize] as char
+            })
+            .collect()
+    }
+
+    /// Edits between `classes` and `s`, with the textbook dynamic programming.
+    fn levenshtein(classes: &[BaseSet], s: &[u8]) -> usize {
+        let mut prev: Vec<usize> = (0..=s.len()).collect();
+        for (i, c) in classes.iter().enumerate() {
+            let mut cur = vec![i + 1; s.len() + 1];
+            for j in 1..=s.len() {
+                cur[j] = (prev[j - 1] + usize::from(!c.matches(s[j - 1]))).min(prev[j] + 1).min(cur[j - 1] + 1);
+            }
+            prev = cur;
+        }
+        prev[s.len()]
+    }
+
+    #[test]
+    fn distances_agree_with_the_definition() {
+        for (seed, p) in ["GATTACA", "ACGT,N2-3,TG", "(AC|GGT),RY"].iter().enumerate() {
+            let pat = Pattern::parse(p).unwrap();
+            let variants = expand(pat.expr()).unwrap();
+            let s = random_dna(3000, seed as u64);
+            for k in 0..3 {
+                let mut last = 0;
+                for (i, m, d) in find_approx(&s, &pat, k).unwrap() {
+                    assert!(i >= last && d <= k, "pattern {p}, hit {i} {m}");
+                    let best = variants
+                        .iter()
+                        .filter(|v| v.len() == m.len())
+                        .map(|v| v.iter().zip(m.bytes()).filter(|(c, b)| !c.matches(*b)).count())
+                        .min();
+                    assert_eq!(best, Some(d), "pattern {p}, hit {i} {m}");
+                    last = i + m.len();
+                }
+                let mut last = 0;
+                for (i, m, d) in find_approx_edits(&s, &pat, k).unwrap() {
+                    assert!(i >= last && d <= k, "pattern {p}, hit {i} {m}");
+                    let best = variants.iter().map(|v| levenshtein(v, m.as_bytes())).min();
+                    assert_eq!(best, Some(d), "pattern {p}, hit {i} {m}");
+                    last = i + m.len();
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn huge_budgets_are_the_pattern_length() {
+        let s = "ACGTACGTTGCA";
+        for p in ["ACG", "AC,N1-3"] {
+            assert_eq!(approx(s, p, usize::MAX), approx(s, p, 6), "{}", p);
+            assert_eq!(edits(s, p, usize::MAX), edits(s, p, 6), "{}", p);
+        }
+    }
+
+    #[test]
+    fn errors() {
+        let err = |p: &str| find_approx("A", &Pattern::parse(p).unwrap(), 1).err();
+        assert_eq!(err("A2-"), Some(ApproxError::OpenRange));
+        assert_eq!(err("A65"), Some(ApproxError::TooLong));
+        assert_eq!(err("N50-60,N5-10"), Some(ApproxError::TooLong));
+        assert_eq!(err("(A|C)1-10,(G|T)1-10,(A|C)1-10,(G|T)1-10"), Some(ApproxError::TooManyVariants));
+        assert_eq!(err("N64"), None);
+    }
+}
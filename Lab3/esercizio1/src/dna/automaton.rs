@@ -0,0 +1,226 @@
+// Compilation of a pattern into a DFA: Thompson NFA first, then subset construction.
+//
+// The alphabet is the 16 IUPAC masks a byte of the sequence may have (symbol 0 is any
+// byte that is not a nucleotide code), so a DFA state has exactly 16 transitions and
+// a step is a single table lookup.
+
+use std::collections::HashMap;
+
+use super::pattern::{BaseSet, Expr, PatternError, PatternErrorKind};
+
+pub(crate) const SYMBOLS: usize = 16;
+/// State with no way out, only reachable by an anchored DFA.
+pub(crate) const DEAD: u32 = 0;
+/// Upper bound to the subset construction, past it the pattern is rejected.
+const MAX_STATES: usize = 100_000;
+
+/// Symbol of the alphabet for a byte of the sequence.
+pub(crate) fn symbol(b: u8) -> usize {
+    BaseSet::from_iupac(b).map_or(0, |s| s.bits() as usize)
+}
+
+#[derive(Debug, Clone)]
+enum NfaState {
+    Set(BaseSet, u32),
+    Split(Vec<u32>),
+    /// End of the pattern with the given index.
+    Match(u32),
+}
+
+struct Nfa {
+    states: Vec<NfaState>,
+    start: u32,
+}
+
+impl Nfa {
+    /// NFA of the union of `exprs`, each one ending in its own `Match` state.
+    /// `reverse` builds the NFA of the reversed language, used to find where matches start.
+    fn compile(exprs: &[&Expr], reverse: bool) -> Nfa {
+        let mut nfa = Nfa { states: Vec::new(), start: 0 };
+        let entries: Vec<u32> = exprs
+            .iter()
+            .enumerate()
+            .map(|(id, e)| {
+                let end = nfa.push(NfaState::Match(id as u32));
+                nfa.add(e, end, reverse)
+            })
+            .collect();
+        nfa.start = match entries[..] {
+            [single] => single,
+            _ => nfa.push(NfaState::Split(entries)),
+        };
+        nfa
+    }
+
+    fn push(&mut self, s: NfaState) -> u32 {
+        self.states.push(s);
+        (self.states.len() - 1) as u32
+    }
+
+    /// Adds the states matching `expr` and then going on to `next`, returns the entry state.
+    fn add(&mut self, expr: &Expr, next: u32, reverse: bool) -> u32 {
+        match expr {
+            Expr::Set(set) => self.push(NfaState::Set(*set, next)),
+            Expr::Concat(items) => {
+                // built from the last item backwards, so each one knows its successor
+                let mut cur = next;
+                if reverse {
+                    for e in items {
+                        cur = self.add(e, cur, reverse);
+                    }
+                } else {
+                    for e in items.iter().rev() {
+                        cur = self.add(e, cur, reverse);
+                    }
+                }
+                cur
+            }
+            Expr::Alt(alts) => {
+                let entries = alts.iter().map(|e| self.add(e, next, reverse)).collect();
+                self.push(NfaState::Split(entries))
+            }
+            Expr::Repeat { expr, min, max } => {
+                let mut cur = match max {
+                    Some(max) => {
+                        let mut cur = next;
+                        for _ in *min..*max {
+                            let entry = self.add(expr, cur, reverse);
+                            cur = self.push(NfaState::Split(vec![entry, next]));
+                        }
+                        cur
+                    }
+                    None => {
+                        let lp = self.push(NfaState::Split(vec![]));
+                        let entry = self.add(expr, lp, reverse);
+                        self.states[lp as usize] = NfaState::Split(vec![entry, next]);
+                        lp
+                    }
+                };
+                for _ in 0..*min {
+                    cur = self.add(expr, cur, reverse);
+                }
+                cur
+            }
+        }
+    }
+
+    /// Sorted set of the `Set`/`Match` states reachable from `from` without consuming input.
+    /// `seen` must be all false and is left that way, so it can be reused between calls
+    /// without paying for the size of the whole NFA each time.
+    fn closure(&self, from: Vec<u32>, seen: &mut [bool]) -> Vec<u32> {
+        let mut stack = from;
+        let mut visited = Vec::new();
+        let mut out = Vec::new();
+        while let Some(id) = stack.pop() {
+            if std::mem::replace(&mut seen[id as usize], true) {
+                continue;
+            }
+            visited.push(id);
+            match &self.states[id as usize] {
+                NfaState::Split(next) => stack.extend(next.iter().copied()),
+                _ => out.push(id),
+            }
+        }
+        for id in visited {
+            seen[id as usize] = false;
+        }
+        out.sort_unstable();
+        out
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub(crate) struct Dfa {
+    trans: Vec<[u32; SYMBOLS]>,
+    /// For each state the sorted indexes of the patterns that end there.
+    accept: Vec<Box<[u32]>>,
+    start: u32,
+}
+
+impl Dfa {
+    /// Anchored DFA: matches only starting from the first byte fed to it.
+    pub(crate) fn forward(exprs: &[&Expr]) -> Result<Dfa, PatternError> {
+        Dfa::build(&Nfa::compile(exprs, false), false)
+    }
+
+    /// Unanchored DFA of the reversed patterns: fed with the sequence from the end, it is
+    /// in an accepting state right after byte `i` iff a match starts at `i`.
+    /// For literal patterns this is the Aho-Corasick automaton of the reversed strings,
+    /// with the failure links already resolved into transitions.
+    pub(crate) fn reverse(exprs: &[&Expr]) -> Result<Dfa, PatternError> {
+        Dfa::build(&Nfa::compile(exprs, true), true)
+    }
+
+    fn build(nfa: &Nfa, unanchored: bool) -> Result<Dfa, PatternError> {
+        let mut seen = vec![false; nfa.states.len()];
+        let start_set = nfa.closure(vec![nfa.start], &mut seen);
+        let mut ids: HashMap<Vec<u32>, u32> = HashMap::new();
+        let mut sets: Vec<Vec<u32>> = Vec::new();
+        let mut dfa = Dfa { trans: Vec::new(), accept: Vec::new(), start: 0 };
+
+        let mut intern = |set: Vec<u32>, dfa: &mut Dfa, sets: &mut Vec<Vec<u32>>| -> Result<u32, PatternError> {
+            if let Some(&id) = ids.get(&set) {
+                return Ok(id);
+            }
+            if sets.len() >= MAX_STATES {
+                return Err(PatternError { pos: 0, kind: PatternErrorKind::TooComplex });
+            }
+            let id = sets.len() as u32;
+            let accept: Box<[u32]> = set
+                .iter()
+                .filter_map(|&s| match nfa.states[s as usize] {
+                    NfaState::Match(id) => Some(id),
+                    _ => None,
+                })
+                .collect();
+            dfa.accept.push(accept);
+            dfa.trans.push([DEAD; SYMBOLS]);
+            ids.insert(set.clone(), id);
+            sets.push(set);
+            Ok(id)
+        };
+
+        intern(Vec::new(), &mut dfa, &mut sets)?;
+        dfa.start = intern(start_set, &mut dfa, &mut sets)?;
+
+        let mut todo = 1;
+        while todo < sets.len() {
+            for sym in 0..SYMBOLS {
+                let mut targets: Vec<u32> = sets[todo]
+                    .iter()
+                    .filter_map(|&s| match &nfa.states[s as usize] {
+                        NfaState::Set(set, next) if set.matches_symbol(sym as u8) => Some(*next),
+                        _ => None,
+                    })
+                    .collect();
+                if unanchored {
+                    targets.push(nfa.start);
+                }
+                let next = intern(nfa.closure(targets, &mut seen), &mut dfa, &mut sets)?;
+                dfa.trans[todo][sym] = next;
+            }
+            todo += 1;
+        }
+        Ok(dfa)
+    }
+
+    pub(crate) fn start(&self) -> u32 {
+        self.start
+    }
+
+    #[inline]
+    pub(crate) fn next(&self, state: u32, b: u8) -> u32 {
+        self.trans[state as usize][symbol(b)]
+    }
+
+    #[inline]
+    pub(crate) fn is_accept(&self, state: u32) -> bool {
+        !self.accept[state as usize].is_empty()
+    }
+
+    /// Patterns ending in `state`, in increasing order.
+    #[inline]
+    pub(crate) fn accepts(&self, state: u32) -> &[u32] {
+        &self.accept[state as usize]
+    }
+}
@@ -0,0 +1,212 @@
+// Editing of the matches of a pattern.
+//
+// `matches_mut` hands out one `&mut str` per match, all alive at the same time: the string
+// is cut with `split_at_mut` at the match boundaries, so the views are disjoint and the
+// borrow checker is happy. They allow edits that keep the length (see `mask_in_place` and
+// `reverse_complement_in_place`); `edit_matches` rebuilds the string and allows any edit.
+
+use super::pattern::Pattern;
+use super::strand::{complement, reverse_complement};
+
+/// Reverse complement of a string, in place, as `reverse_complement`: characters that
+/// aren't ASCII are reversed with the others and kept as they are.
+pub fn reverse_complement_in_place(s: &mut str) {
+    // SAFETY: reversing the bytes leaves the bytes of every multi-byte character
+    // backwards, and the loop puts them back in order before s is used again; complement
+    // maps ASCII bytes to ASCII bytes. Nothing in between can panic, so s is UTF-8 again
+    // whenever it can be seen
+    let bytes = unsafe { s.as_bytes_mut() };
+    bytes.reverse();
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i].is_ascii() {
+            bytes[i] = complement(bytes[i]);
+            i += 1;
+        } else {
+            // continuation bytes (10xxxxxx), then the leading one
+            let start = i;
+            while i < bytes.len() && bytes[i] & 0xc0 == 0x80 {
+                i += 1;
+            }
+            i = (i + 1).min(bytes.len());
+            bytes[start..i].reverse();
+        }
+    }
+}
+
+/// Replaces every ASCII character with `with` (e.g. `b'N'`), keeping the length: the
+/// characters that aren't ASCII are left as they are. Panics if `with` isn't ASCII.
+pub fn mask_in_place(s: &mut str, with: u8) {
+    assert!(with.is_ascii(), "mask with a non ASCII byte");
+    // SAFETY: only ASCII bytes are written, over ASCII bytes, so s stays UTF-8
+    for b in unsafe { s.as_bytes_mut() } {
+        if b.is_ascii() {
+            *b = with;
+        }
+    }
+}
+
+/// Mutable views over the non overlapping matches of `pat`, with their positions.
+pub fn matches_mut<'s>(s: &'s mut str, pat: &Pattern) -> Vec<(usize, &'s mut str)> {
+    let ranges: Vec<(usize, usize)> = pat.find_iter(s).map(|(i, m)| (i, i + m.len())).collect();
+    let mut out = Vec::with_capacity(ranges.len());
+    let mut rest = s;
+    let mut offset = 0;
+    for (start, end) in ranges {
+        let (_, tail) = rest.split_at_mut(start - offset);
+        let (m, tail) = tail.split_at_mut(end - start);
+        out.push((start, m));
+        rest = tail;
+        offset = end;
+    }
+    out
+}
+
+/// A match being edited by `edit_matches`. The operations apply to the current text of
+/// the edit, so they can be combined (e.g. replace and then reverse complement).
+#[derive(Debug)]
+pub struct Edit<'a> {
+    pos: usize,
+    matched: &'a str,
+    text: Option<String>,
+}
+
+impl Edit<'_> {
+    /// Position of the match in the original string.
+    pub fn pos(&self) -> usize {
+        self.pos
+    }
+
+    /// Matched text, before any edit.
+    pub fn matched(&self) -> &str {
+        self.matched
+    }
+
+    /// Text that will take the place of the match.
+    pub fn text(&self) -> &str {
+        self.text.as_deref().unwrap_or(self.matched)
+    }
+
+    pub fn replace(&mut self, with: impl Into<String>) {
+        self.text = Some(with.into());
+    }
+
+    pub fn delete(&mut self) {
+        self.replace(String::new());
+    }
+
+    pub fn reverse_complement(&mut self) {
+        self.text = Some(reverse_complement(self.text()));
+    }
+
+    /// Hard mask: every base becomes `N`; characters that aren't ASCII are kept, as in
+    /// `mask_in_place`.
+    pub fn mask(&mut self) {
+        self.text = Some(self.text().chars().map(|c| if c.is_ascii() { 'N' } else { c }).collect());
+    }
+
+    /// Soft mask: bases in lowercase.
+    pub fn soft_mask(&mut self) {
+        self.text = Some(self.text().to_ascii_lowercase());
+    }
+}
+
+/// Calls `f` on every non overlapping match of `pat` in `s` and writes back the edited
+/// matches, that may change the length of `s`. Returns the number of matches.
+pub fn edit_matches<F>(s: &mut String, pat: &Pattern, mut f: F) -> usize
+where
+    F: FnMut(&mut Edit),
+{
+    let mut out = String::with_capacity(s.len());
+    let mut last = 0;
+    let mut count = 0;
+    for (pos, matched) in pat.find_iter(s) {
+        let mut edit = Edit { pos, matched, text: None };
+        f(&mut edit);
+        out.push_str(&s[last..pos]);
+        out.push_str(edit.text());
+        last = pos + matched.len();
+        count += 1;
+    }
+    if count > 0 {
+        out.push_str(&s[last..]);
+        *s = out;
+    }
+    count
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_complement_in_place_ascii() {
+        let mut s = String::from("GATTACA");
+        reverse_complement_in_place(&mut s);
+        assert_eq!(s, "TGTAATC");
+    }
+
+    #[test]
+    fn in_place_edits_keep_non_ascii_characters() {
+        let mut s = String::from("Aé→C𝄞g");
+        reverse_complement_in_place(&mut s);
+        assert_eq!(s, "c𝄞G→éT");
+        mask_in_place(&mut s, b'N');
+        assert_eq!(s, "N𝄞N→éN");
+    }
+
+    #[test]
+    fn disjoint_mutable_views() {
+        let pat = Pattern::parse("A1-1,C2-4").unwrap();
+        let mut s = String::from("AACCGGTAACC");
+        let mut views = matches_mut(&mut s, &pat);
+        assert_eq!(views.iter().map(|(i, m)| (*i, m.to_string())).collect::<Vec<_>>(), vec![(1, "ACC".to_string()), (8, "ACC".to_string())]);
+        mask_in_place(views[0].1, b'N');
+        views[1].1.make_ascii_lowercase();
+        assert_eq!(s, "ANNNGGTAacc");
+    }
+
+    #[test]
+    fn edits_changing_length() {
+        let pat = Pattern::parse("A1-1,C2-4").unwrap();
+        let mut s = String::from("AACCGGTAACCC");
+        let n = edit_matches(&mut s, &pat, |e| match e.pos() {
+            1 => e.replace("T"),
+            _ => e.reverse_complement(),
+        });
+        assert_eq!(n, 2);
+        assert_eq!(s, "ATGGTAGGGT");
+
+        let n = edit_matches(&mut s, &Pattern::parse("G2-").unwrap(), |e| {
+            e.replace("TT");
+            e.soft_mask();
+        });
+        assert_eq!(n, 2);
+        assert_eq!(s, "ATttTAttT");
+    }
+
+    #[test]
+    fn edits_of_non_ascii_text() {
+        let pat = Pattern::parse("C2").unwrap();
+        let mut s = String::from("ACCA");
+        edit_matches(&mut s, &pat, |e| {
+            e.replace("Gé");
+            e.reverse_complement();
+        });
+        assert_eq!(s, "AéCA");
+        edit_matches(&mut s, &Pattern::parse("C").unwrap(), |e| {
+            e.replace("üC");
+            e.mask();
+        });
+        assert_eq!(s, "AéüNA");
+    }
+
+    #[test]
+    fn mask_and_delete() {
+        let pat = Pattern::parse("T2-").unwrap();
+        let mut s = String::from("ATTTCGTTA");
+        edit_matches(&mut s, &pat, |e| if e.pos() == 1 { e.mask() } else { e.delete() });
+        assert_eq!(s, "ANNNCGA");
+        assert_eq!(edit_matches(&mut s, &pat, |e| e.delete()), 0);
+    }
+}
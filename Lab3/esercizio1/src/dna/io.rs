@@ -0,0 +1,360 @@
+// Streaming readers for FASTA and FASTQ files.
+//
+// Each record is read in memory on its own, with the lines of a wrapped sequence joined,
+// so a pattern is searched on the whole sequence of the record (matches across line
+// breaks included) and positions are relative to the start of the record.
+
+use std::error::Error;
+use std::fmt;
+use std::io::{self, BufRead};
+
+use super::multi::{MatchKind, MultiMatches, MultiPatternSearcher};
+use super::pattern::{BaseSet, Pattern};
+use super::search::Matches;
+
+/// Characters accepted in a sequence (case insensitive).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Alphabet {
+    /// A C G T and N.
+    Dna,
+    /// All the IUPAC codes, U included.
+    #[default]
+    Iupac,
+    /// No check.
+    Any,
+}
+
+impl Alphabet {
+    pub fn contains(self, b: u8) -> bool {
+        match self {
+            Alphabet::Dna => matches!(b.to_ascii_uppercase(), b'A' | b'C' | b'G' | b'T' | b'N'),
+            Alphabet::Iupac => BaseSet::from_iupac(b).is_some(),
+            Alphabet::Any => true,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Record {
+    pub id: String,
+    pub description: Option<String>,
+    pub seq: String,
+    /// FASTQ only: one Phred+33 quality per base.
+    pub qual: Option<Vec<u8>>,
+}
+
+impl Record {
+    /// Phred scores of the bases, empty for a FASTA record.
+    pub fn phred_scores(&self) -> impl Iterator<Item = u8> + '_ {
+        self.qual.iter().flatten().map(|q| q.saturating_sub(33))
+    }
+
+    /// Matches of `pat` in the sequence, positions relative to the record.
+    pub fn find_iter<'p>(&self, pat: &'p Pattern) -> Matches<'p, '_> {
+        pat.find_iter(&self.seq)
+    }
+
+    pub fn find_all_iter<'m>(&self, searcher: &'m MultiPatternSearcher, kind: MatchKind) -> MultiMatches<'m, '_> {
+        searcher.find_iter(&self.seq, kind)
+    }
+}
+
+#[derive(Debug)]
+pub enum ReadErrorKind {
+    Io(io::Error),
+    /// Data before the first header, or a header without the `>`/`@` marker.
+    MissingHeader,
+    /// FASTQ record without the `+` separator line.
+    MissingSeparator,
+    /// Character not in the alphabet of the reader.
+    InvalidBase(char),
+    /// FASTQ qualities not as many as the bases.
+    QualityLength { expected: usize, found: usize },
+}
+
+/// Error of a reader, `line` counts from 1.
+#[derive(Debug)]
+pub struct ReadError {
+    pub line: usize,
+    pub kind: ReadErrorKind,
+}
+
+impl fmt::Display for ReadError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: ", self.line)?;
+        match &self.kind {
+            ReadErrorKind::Io(e) => write!(f, "{e}"),
+            ReadErrorKind::MissingHeader => write!(f, "expected a record header"),
+            ReadErrorKind::MissingSeparator => write!(f, "expected the '+' line"),
+            ReadErrorKind::InvalidBase(c) => write!(f, "invalid base {c:?}"),
+            ReadErrorKind::QualityLength { expected, found } => {
+                write!(f, "{found} qualities for {expected} bases")
+            }
+        }
+    }
+}
+
+impl Error for ReadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match &self.kind {
+            ReadErrorKind::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+/// Lines of the input without the line terminator, with their number.
+struct Lines<R> {
+    input: R,
+    buf: String,
+    line: usize,
+    peeked: bool,
+}
+
+impl<R: BufRead> Lines<R> {
+    fn new(input: R) -> Self {
+        Lines { input, buf: String::new(), line: 0, peeked: false }
+    }
+
+    fn error(&self, kind: ReadErrorKind) -> ReadError {
+        ReadError { line: self.line, kind }
+    }
+
+    /// Next line and its number, `None` at the end of the input.
+    fn next(&mut self) -> Result<Option<(usize, &str)>, ReadError> {
+        if std::mem::take(&mut self.peeked) {
+            return Ok(Some((self.line, &self.buf)));
+        }
+        self.buf.clear();
+        match self.input.read_line(&mut self.buf) {
+            Ok(0) => Ok(None),
+            Ok(_) => {
+                self.line += 1;
+                let len = self.buf.trim_end_matches(['\n', '\r']).len();
+                self.buf.truncate(len);
+                Ok(Some((self.line, &self.buf)))
+            }
+            Err(e) => Err(ReadError { line: self.line + 1, kind: ReadErrorKind::Io(e) }),
+        }
+    }
+
+    /// The line just returned by `next` will be returned again.
+    fn push_back(&mut self) {
+        self.peeked = true;
+    }
+
+    /// Next non blank line.
+    fn next_non_blank(&mut self) -> Result<Option<(usize, &str)>, ReadError> {
+        loop {
+            match self.next()? {
+                None => return Ok(None),
+                Some((_, l)) if l.trim().is_empty() => {}
+                Some(_) => break,
+            }
+        }
+        self.push_back();
+        self.next()
+    }
+}
+
+fn header(line: &str) -> (String, Option<String>) {
+    let line = line.trim();
+    match line.split_once(char::is_whitespace) {
+        Some((id, desc)) => (id.to_string(), Some(desc.trim().to_string())),
+        None => (line.to_string(), None),
+    }
+}
+
+fn push_bases(seq: &mut String, line: &str, alphabet: Alphabet, line_no: usize) -> Result<(), ReadError> {
+    let line = line.trim();
+    if let Some(c) = line.chars().find(|&c| !c.is_ascii() || !alphabet.contains(c as u8)) {
+        return Err(ReadError { line: line_no, kind: ReadErrorKind::InvalidBase(c) });
+    }
+    seq.push_str(line);
+    Ok(())
+}
+
+/// Iterator over the records of a FASTA input.
+pub struct FastaReader<R> {
+    lines: Lines<R>,
+    alphabet: Alphabet,
+    failed: bool,
+}
+
+impl<R: BufRead> FastaReader<R> {
+    pub fn new(input: R) -> Self {
+        FastaReader { lines: Lines::new(input), alphabet: Alphabet::default(), failed: false }
+    }
+
+    pub fn alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    fn read_record(&mut self) -> Result<Option<Record>, ReadError> {
+        let Some((_, line)) = self.lines.next_non_blank()? else {
+            return Ok(None);
+        };
+        let Some(h) = line.strip_prefix('>') else {
+            return Err(self.lines.error(ReadErrorKind::MissingHeader));
+        };
+        let (id, description) = header(h);
+        let mut seq = String::new();
+        while let Some((n, line)) = self.lines.next()? {
+            if line.starts_with('>') {
+                self.lines.push_back();
+                break;
+            }
+            push_bases(&mut seq, line, self.alphabet, n)?;
+        }
+        Ok(Some(Record { id, description, seq, qual: None }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastaReader<R> {
+    type Item = Result<Record, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        // after an error the position in the input is unknown, so the reader stops
+        if self.failed {
+            return None;
+        }
+        let res = self.read_record().transpose();
+        self.failed = matches!(res, Some(Err(_)));
+        res
+    }
+}
+
+/// Iterator over the records of a FASTQ input. Sequence and qualities may be wrapped on
+/// several lines, the qualities end when they are as many as the bases.
+pub struct FastqReader<R> {
+    lines: Lines<R>,
+    alphabet: Alphabet,
+    failed: bool,
+}
+
+impl<R: BufRead> FastqReader<R> {
+    pub fn new(input: R) -> Self {
+        FastqReader { lines: Lines::new(input), alphabet: Alphabet::default(), failed: false }
+    }
+
+    pub fn alphabet(mut self, alphabet: Alphabet) -> Self {
+        self.alphabet = alphabet;
+        self
+    }
+
+    fn read_record(&mut self) -> Result<Option<Record>, ReadError> {
+        let Some((_, line)) = self.lines.next_non_blank()? else {
+            return Ok(None);
+        };
+        let Some(h) = line.strip_prefix('@') else {
+            return Err(self.lines.error(ReadErrorKind::MissingHeader));
+        };
+        let (id, description) = header(h);
+
+        let mut seq = String::new();
+        loop {
+            match self.lines.next()? {
+                Some((_, line)) if line.starts_with('+') => break,
+                Some((n, line)) => push_bases(&mut seq, line, self.alphabet, n)?,
+                None => return Err(self.lines.error(ReadErrorKind::MissingSeparator)),
+            }
+        }
+
+        let mut qual = Vec::with_capacity(seq.len());
+        while qual.len() < seq.len() {
+            match self.lines.next()? {
+                Some((_, line)) => qual.extend_from_slice(line.trim().as_bytes()),
+                None => break,
+            }
+        }
+        if qual.len() != seq.len() {
+            let kind = ReadErrorKind::QualityLength { expected: seq.len(), found: qual.len() };
+            return Err(self.lines.error(kind));
+        }
+        Ok(Some(Record { id, description, seq, qual: Some(qual) }))
+    }
+}
+
+impl<R: BufRead> Iterator for FastqReader<R> {
+    type Item = Result<Record, ReadError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.failed {
+            return None;
+        }
+        let res = self.read_record().transpose();
+        self.failed = matches!(res, Some(Err(_)));
+        res
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FASTA: &str = ">chr1 first record\nACGTAC\nCCGT\n\n>chr2\nTTTT\nAAAC\n";
+
+    #[test]
+    fn fasta_records() {
+        let records: Vec<_> = FastaReader::new(FASTA.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, "chr1");
+        assert_eq!(records[0].description.as_deref(), Some("first record"));
+        assert_eq!(records[0].seq, "ACGTACCCGT");
+        assert_eq!(records[1].id, "chr2");
+        assert_eq!(records[1].description, None);
+        assert_eq!(records[1].seq, "TTTTAAAC");
+    }
+
+    #[test]
+    fn matches_across_line_breaks_relative_to_record() {
+        let pat = Pattern::parse("A1-1,C2-4").unwrap();
+        let hits: Vec<_> = FastaReader::new(FASTA.as_bytes())
+            .map(|r| r.unwrap())
+            .map(|r| (r.id.clone(), r.find_iter(&pat).map(|(i, m)| (i, m.to_string())).collect::<Vec<_>>()))
+            .collect();
+        assert_eq!(hits[0], ("chr1".to_string(), vec![(4, "ACCC".to_string())]));
+        assert_eq!(hits[1], ("chr2".to_string(), vec![]));
+    }
+
+    #[test]
+    fn fasta_errors() {
+        let err = FastaReader::new("ACGT\n".as_bytes()).next().unwrap().unwrap_err();
+        assert!(matches!(err.kind, ReadErrorKind::MissingHeader));
+        assert_eq!(err.line, 1);
+
+        let mut reader = FastaReader::new(">a\nACGT\nACXT\n>b\nAC\n".as_bytes()).alphabet(Alphabet::Dna);
+        let err = reader.next().unwrap().unwrap_err();
+        assert!(matches!(err.kind, ReadErrorKind::InvalidBase('X')));
+        assert_eq!(err.line, 3);
+        assert!(reader.next().is_none());
+
+        let ok = FastaReader::new(">a\nACRY\n".as_bytes()).next().unwrap();
+        assert!(ok.is_ok());
+        let err = FastaReader::new(">a\nACRY\n".as_bytes()).alphabet(Alphabet::Dna).next().unwrap();
+        assert!(err.is_err());
+    }
+
+    #[test]
+    fn fastq_records() {
+        let input = "@r1 lane 1\nACGT\n+\nIIII\n@r2\nAC\nGT\n+r2\n!!\n#+\n";
+        let records: Vec<_> = FastqReader::new(input.as_bytes()).collect::<Result<_, _>>().unwrap();
+        assert_eq!(records.len(), 2);
+        assert_eq!(records[0].id, "r1");
+        assert_eq!(records[0].description.as_deref(), Some("lane 1"));
+        assert_eq!(records[0].phred_scores().collect::<Vec<_>>(), vec![40; 4]);
+        assert_eq!(records[1].seq, "ACGT");
+        assert_eq!(records[1].qual.as_deref(), Some(&b"!!#+"[..]));
+    }
+
+    #[test]
+    fn fastq_errors() {
+        let err = FastqReader::new("@r1\nACGT\n+\nII\n".as_bytes()).next().unwrap().unwrap_err();
+        assert!(matches!(err.kind, ReadErrorKind::QualityLength { expected: 4, found: 2 }));
+        let err = FastqReader::new("@r1\nACGT\n".as_bytes()).next().unwrap().unwrap_err();
+        assert!(matches!(err.kind, ReadErrorKind::MissingSeparator));
+        let err = FastqReader::new(">r1\nACGT\n".as_bytes()).next().unwrap().unwrap_err();
+        assert!(matches!(err.kind, ReadErrorKind::MissingHeader));
+    }
+}
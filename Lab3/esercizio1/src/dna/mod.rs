@@ -0,0 +1,16 @@
+pub mod approx;
+mod automaton;
+pub mod edit;
+pub mod io;
+pub mod multi;
+pub mod parallel;
+pub mod pattern;
+pub mod search;
+pub mod strand;
+
+pub use approx::{find_approx, find_approx_edits, ApproxError, ApproxMatches};
+pub use multi::{MatchKind, MultiMatches, MultiPatternSearcher};
+pub use parallel::par_subsequences;
+pub use pattern::{Pattern, PatternError};
+pub use search::Matches;
+pub use strand::{reverse_complement, Strand, StrandMatches};
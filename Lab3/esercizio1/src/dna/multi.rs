@@ -0,0 +1,166 @@
+use std::collections::VecDeque;
+
+use super::automaton::Dfa;
+use super::pattern::{Pattern, PatternError};
+use super::search::Scanner;
+
+/// How the matches of different patterns may overlap.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum MatchKind {
+    /// At the leftmost position where any pattern matches, the longest match among all
+    /// the patterns (the lowest index on ties); the search goes on after its end.
+    #[default]
+    LeftmostLongest,
+    /// The longest match of every pattern at every position where it matches.
+    Overlapping,
+    /// Matches of the same pattern never overlap, matches of different patterns may:
+    /// the same result of searching each pattern on its own.
+    PerPattern,
+}
+
+/// Searches many patterns at once: a single automaton over all of them, so the sequence
+/// is scanned once however many patterns there are.
+#[derive(Debug, Clone)]
+pub struct MultiPatternSearcher {
+    patterns: Vec<Pattern>,
+    forward: Dfa,
+    reverse: Dfa,
+}
+
+impl MultiPatternSearcher {
+    /// Fails only if the combined automaton would be too large.
+    pub fn new(patterns: impl IntoIterator<Item = Pattern>) -> Result<Self, PatternError> {
+        let patterns: Vec<Pattern> = patterns.into_iter().collect();
+        let exprs: Vec<_> = patterns.iter().map(Pattern::expr).collect();
+        Ok(MultiPatternSearcher {
+            forward: Dfa::forward(&exprs)?,
+            reverse: Dfa::reverse(&exprs)?,
+            patterns,
+        })
+    }
+
+    pub fn patterns(&self) -> &[Pattern] {
+        &self.patterns
+    }
+
+    /// Iterator of `(position, matched slice, pattern index)`, ordered by position and
+    /// then by pattern index.
+    pub fn find_iter<'m, 's>(&'m self, s: &'s str, kind: MatchKind) -> MultiMatches<'m, 's> {
+        MultiMatches {
+            scanner: Scanner::new(&self.forward, &self.reverse, s, 0),
+            kind,
+            last: vec![usize::MAX; self.patterns.len()],
+            next_allowed: vec![0; self.patterns.len()],
+            pending: VecDeque::new(),
+        }
+    }
+}
+
+pub struct MultiMatches<'m, 's> {
+    scanner: Scanner<'m, 's>,
+    kind: MatchKind,
+    last: Vec<usize>,
+    /// `PerPattern` only: where the next match of each pattern may start.
+    next_allowed: Vec<usize>,
+    pending: VecDeque<(usize, usize, usize)>,
+}
+
+impl<'s> Iterator for MultiMatches<'_, 's> {
+    type Item = (usize, &'s str, usize);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        while self.pending.is_empty() {
+            let start = self.scanner.next_start()?;
+            match self.kind {
+                MatchKind::LeftmostLongest => {
+                    let (end, id) = self.scanner.longest(start)?;
+                    self.scanner.advance(end);
+                    self.pending.push_back((start, end, id as usize));
+                }
+                MatchKind::Overlapping => {
+                    let pending = &mut self.pending;
+                    self.scanner.longest_each(start, &mut self.last, |id, end| {
+                        pending.push_back((start, end, id as usize));
+                    });
+                    self.scanner.advance(start + 1);
+                }
+                MatchKind::PerPattern => {
+                    let (pending, allowed) = (&mut self.pending, &mut self.next_allowed);
+                    self.scanner.longest_each(start, &mut self.last, |id, end| {
+                        let id = id as usize;
+                        if allowed[id] <= start {
+                            allowed[id] = end;
+                            pending.push_back((start, end, id));
+                        }
+                    });
+                    self.scanner.advance(start + 1);
+                }
+            }
+        }
+        let (start, end, id) = self.pending.pop_front()?;
+        Some((start, &self.scanner.s[start..end], id))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn searcher(patterns: &[&str]) -> MultiPatternSearcher {
+        MultiPatternSearcher::new(patterns.iter().map(|p| Pattern::parse(p).unwrap())).unwrap()
+    }
+
+    #[test]
+    fn leftmost_longest() {
+        let m = searcher(&["A1-1,C2-4", "G1-1,T1-4", "C2"]);
+        let found: Vec<_> = m.find_iter("AACCGGTAACC", MatchKind::LeftmostLongest).collect();
+        assert_eq!(found, vec![(1, "ACC", 0), (5, "GT", 1), (8, "ACC", 0)]);
+    }
+
+    #[test]
+    fn ties_go_to_the_lowest_index() {
+        let m = searcher(&["C2", "[CG]2", "A,C"]);
+        let found: Vec<_> = m.find_iter("ACCGG", MatchKind::LeftmostLongest).collect();
+        assert_eq!(found, vec![(0, "AC", 2), (2, "CG", 1)]);
+    }
+
+    #[test]
+    fn overlapping() {
+        let m = searcher(&["A1-2", "A,C"]);
+        let found: Vec<_> = m.find_iter("AAC", MatchKind::Overlapping).collect();
+        assert_eq!(found, vec![(0, "AA", 0), (1, "A", 0), (1, "AC", 1)]);
+    }
+
+    #[test]
+    fn per_pattern_is_each_pattern_on_its_own() {
+        let sources = ["A1-1,C2-4", "G1-1,T1-4", "[AC]2-", "N3"];
+        let m = searcher(&sources);
+        let s = "AACCGGTAACCTTTGACGTTAGC";
+        let mut found: Vec<_> = m.find_iter(s, MatchKind::PerPattern).collect();
+        found.sort_by_key(|&(pos, _, id)| (id, pos));
+        let expected: Vec<_> = sources
+            .iter()
+            .enumerate()
+            .flat_map(|(id, p)| Pattern::parse(p).unwrap().find_iter(s).map(move |(pos, m)| (pos, m, id)).collect::<Vec<_>>())
+            .collect();
+        assert_eq!(found, expected);
+    }
+
+    #[test]
+    fn many_motifs() {
+        // every 6-mer of a sequence, searched at once
+        let s = "ACGTTGCAAGGCTTACGATCGGATCCATGCA";
+        let motifs: Vec<String> = (0..s.len() - 6).step_by(3).map(|i| s[i..i + 6].to_string()).collect();
+        let m = MultiPatternSearcher::new(motifs.iter().map(|p| Pattern::parse(p).unwrap())).unwrap();
+        let found: Vec<_> = m.find_iter(s, MatchKind::Overlapping).collect();
+        for (id, motif) in motifs.iter().enumerate() {
+            assert!(found.contains(&(id * 3, motif.as_str(), id)));
+        }
+    }
+
+    #[test]
+    fn no_patterns() {
+        let m = searcher(&[]);
+        assert_eq!(m.find_iter("ACGT", MatchKind::Overlapping).next(), None);
+    }
+}
@@ -0,0 +1,124 @@
+use std::thread;
+
+use super::pattern::Pattern;
+
+/// Same result as `pat.find_iter(s).collect()`, with the search split among `threads`
+/// threads.
+///
+/// `s` is cut in `threads` chunks; each chunk is searched on its own over a window that
+/// goes past its end by the longest possible match, so the matches that start in the chunk
+/// are found whole even when they cross the boundary. The matches starting in a chunk are
+/// found as if the search started at the beginning of the chunk: when the last match of
+/// the previous chunk ends inside it, the merge searches again from that end until it
+/// meets a match of the chunk, from there on the two searches agree.
+/// Patterns with an open range have no bound to the overlap and are searched sequentially.
+pub fn par_subsequences<'a>(s: &'a str, pat: &Pattern, threads: usize) -> Vec<(usize, &'a str)> {
+    let Some(max_len) = pat.max_len() else {
+        return pat.find_iter(s).collect();
+    };
+    let threads = threads.max(1);
+    let chunk = s.len().div_ceil(threads).max(1);
+
+    // chunk boundaries on char boundaries, so that slicing never panics
+    let mut bounds: Vec<usize> = (0..threads).map(|k| ceil_char_boundary(s, k * chunk)).collect();
+    bounds.push(s.len());
+    bounds.dedup();
+
+    let chains: Vec<Vec<(usize, usize)>> = thread::scope(|sc| {
+        let handles: Vec<_> = bounds
+            .windows(2)
+            .map(|w| {
+                let (start, end) = (w[0], w[1]);
+                let window_end = ceil_char_boundary(s, end.saturating_add(max_len));
+                sc.spawn(move || {
+                    pat.find_iter(&s[start..window_end])
+                        .map(|(i, m)| (start + i, start + i + m.len()))
+                        .take_while(|&(i, _)| i < end)
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        handles.into_iter().map(|h| h.join().unwrap()).collect()
+    });
+
+    let mut out = Vec::with_capacity(chains.iter().map(Vec::len).sum());
+    let mut pos = 0;
+    for (k, chain) in chains.into_iter().enumerate() {
+        let end = bounds[k + 1];
+        let mut idx = 0;
+        if pos > bounds[k] {
+            // the last match crossed the boundary: search again from its end
+            loop {
+                while chain.get(idx).is_some_and(|&(i, _)| i < pos) {
+                    idx += 1;
+                }
+                let next = chain.get(idx).map_or(end, |&(i, _)| i);
+                match (pos..next).find_map(|i| pat.longest_at(s, i).map(|e| (i, e))) {
+                    Some((i, e)) => {
+                        out.push((i, e));
+                        pos = e;
+                    }
+                    None => break,
+                }
+            }
+        }
+        out.extend_from_slice(&chain[idx..]);
+        if let Some(&(_, e)) = out.last() {
+            pos = pos.max(e);
+        }
+    }
+    out.into_iter().map(|(i, e)| (i, &s[i..e])).collect()
+}
+
+fn ceil_char_boundary(s: &str, mut i: usize) -> usize {
+    if i >= s.len() {
+        return s.len();
+    }
+    while !s.is_char_boundary(i) {
+        i += 1;
+    }
+    i
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn random_dna(len: usize, mut seed: u64) -> String {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                b"ACGT"[((seed >> 33) % 4) as usize] as char
+            })
+            .collect()
+    }
+
+    #[test]
+    fn same_as_sequential() {
+        let patterns = ["A1-1,C2-4", "N3", "A1-3,C1-2", "(A,C)2-4|G3", "[AG]1-3,T1-3,N", "A|A,C,G,T,A,C,G,T"];
+        for (k, p) in patterns.iter().enumerate() {
+            let pat = Pattern::parse(p).unwrap();
+            let s = random_dna(5000, k as u64);
+            let sequential: Vec<_> = pat.find_iter(&s).collect();
+            for threads in [1, 2, 3, 7, 16, 64, 1000] {
+                assert_eq!(par_subsequences(&s, &pat, threads), sequential, "pattern {p}, {threads} threads");
+            }
+        }
+    }
+
+    #[test]
+    fn match_across_every_boundary() {
+        let pat = Pattern::parse("A1-1,C2-4").unwrap();
+        let s = "ACCCC".repeat(20);
+        assert_eq!(par_subsequences(&s, &pat, 13), pat.find_iter(&s).collect::<Vec<_>>());
+    }
+
+    #[test]
+    fn more_threads_than_bytes_and_open_ranges() {
+        let pat = Pattern::parse("A2-").unwrap();
+        assert_eq!(par_subsequences("CAAAT", &pat, 10), vec![(1, "AAA")]);
+        assert_eq!(par_subsequences("", &pat, 4), vec![]);
+        let pat = Pattern::parse("A,T").unwrap();
+        assert_eq!(par_subsequences("ATèAT", &pat, 5), vec![(0, "AT"), (4, "AT")]);
+    }
+}
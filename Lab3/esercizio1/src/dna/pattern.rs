@@ -0,0 +1,697 @@
+// Pattern language for DNA subsequence search.
+//
+//   pattern     := alternative ('|' alternative)*
+//   alternative := element (','? element)*
+//   element     := atom count?
+//   atom        := base | '[' base+ ']' | '(' pattern ')'
+//   count       := number ('-' number?)?
+//
+// `base` is an IUPAC nucleotide code (A C G T U R Y S W K M B D H V N, case insensitive),
+// `A3` means exactly 3, `A1-4` from 1 to 4 and `A2-` at least 2; a missing count means 1.
+// Whitespace between tokens is ignored, so "A1-1, C2-4" is the same as "A1-1,C2-4", and the
+// comma may be left out, so a plain motif like "ACGT" is also a pattern.
+//
+// A search finds the leftmost match and, among the matches starting there, the longest
+// one, so a match is found whenever there is one. The old greedy `find_sub` took as many
+// bases as possible for each element and never gave any back: it agrees when adjacent
+// elements can't match the same base, as in "A1-1,C2-4", and misses matches otherwise:
+// "A1-3,A1-1" matches "AAA" here, where the greedy search found nothing.
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+use std::sync::OnceLock;
+
+use super::automaton::{symbol, Dfa, DEAD};
+use super::search::Matches;
+use super::strand::{Strand, StrandMatches};
+
+/// Set of nucleotides stored as a bitmask (A = 1, C = 2, G = 4, T = 8).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct BaseSet(u8);
+
+impl BaseSet {
+    pub const A: BaseSet = BaseSet(1);
+    pub const C: BaseSet = BaseSet(2);
+    pub const G: BaseSet = BaseSet(4);
+    pub const T: BaseSet = BaseSet(8);
+    pub const NONE: BaseSet = BaseSet(0);
+    pub const ANY: BaseSet = BaseSet(15);
+
+    /// IUPAC code of a nucleotide or of an ambiguity class.
+    pub fn from_iupac(c: u8) -> Option<BaseSet> {
+        let bits = match c.to_ascii_uppercase() {
+            b'A' => 0b0001,
+            b'C' => 0b0010,
+            b'G' => 0b0100,
+            b'T' | b'U' => 0b1000,
+            b'R' => 0b0101,
+            b'Y' => 0b1010,
+            b'S' => 0b0110,
+            b'W' => 0b1001,
+            b'K' => 0b1100,
+            b'M' => 0b0011,
+            b'B' => 0b1110,
+            b'D' => 0b1101,
+            b'H' => 0b1011,
+            b'V' => 0b0111,
+            b'N' => 0b1111,
+            _ => return None,
+        };
+        Some(BaseSet(bits))
+    }
+
+    /// IUPAC code of the set (uppercase, `T` for thymine), `None` for the empty set.
+    pub fn to_iupac(self) -> Option<u8> {
+        b"-ACMGRSVTWYHKDBN".get(self.0 as usize).copied().filter(|&c| c != b'-')
+    }
+
+    /// Set of the complementary nucleotides: A <-> T, C <-> G.
+    pub fn complement(self) -> BaseSet {
+        BaseSet((self.0 & 1) << 3 | (self.0 & 2) << 1 | (self.0 & 4) >> 1 | (self.0 & 8) >> 3)
+    }
+
+    pub fn bits(self) -> u8 {
+        self.0
+    }
+
+    pub fn union(self, other: BaseSet) -> BaseSet {
+        BaseSet(self.0 | other.0)
+    }
+
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    /// Whether the byte `b` of a sequence is matched by this set. An ambiguous base in the
+    /// sequence (e.g. `N`) is matched only by a set that contains all of its nucleotides.
+    pub fn matches(self, b: u8) -> bool {
+        self.matches_symbol(symbol(b) as u8)
+    }
+
+    /// Same as `matches`, for a byte already turned into its mask (0 if not a nucleotide).
+    pub(crate) fn matches_symbol(self, mask: u8) -> bool {
+        mask != 0 && mask & !self.0 == 0
+    }
+}
+
+/// Parsed pattern. `Pattern::parse` is the only way to build one, so every `Expr` inside
+/// a `Pattern` is valid and cannot match the empty string.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Set(BaseSet),
+    Repeat { expr: Box<Expr>, min: u32, max: Option<u32> },
+    Concat(Vec<Expr>),
+    Alt(Vec<Expr>),
+}
+
+impl Expr {
+    /// Same expression with the concatenations reversed and the bases complemented.
+    pub fn reverse_complement(&self) -> Expr {
+        match self {
+            Expr::Set(set) => Expr::Set(set.complement()),
+            Expr::Repeat { expr, min, max } => Expr::Repeat {
+                expr: Box::new(expr.reverse_complement()),
+                min: *min,
+                max: *max,
+            },
+            Expr::Concat(v) => Expr::Concat(v.iter().rev().map(Expr::reverse_complement).collect()),
+            Expr::Alt(v) => Expr::Alt(v.iter().map(Expr::reverse_complement).collect()),
+        }
+    }
+
+    fn nullable(&self) -> bool {
+        match self {
+            Expr::Set(_) => false,
+            Expr::Repeat { expr, min, .. } => *min == 0 || expr.nullable(),
+            Expr::Concat(v) => v.iter().all(Expr::nullable),
+            Expr::Alt(v) => v.iter().any(Expr::nullable),
+        }
+    }
+
+    /// Longest match in bytes, `None` if unbounded.
+    fn max_len(&self) -> Option<usize> {
+        match self {
+            Expr::Set(_) => Some(1),
+            Expr::Repeat { expr, max, .. } => {
+                let max = (*max)? as usize;
+                expr.max_len()?.checked_mul(max)
+            }
+            Expr::Concat(v) => v.iter().try_fold(0usize, |acc, e| acc.checked_add(e.max_len()?)),
+            Expr::Alt(v) => v.iter().try_fold(0usize, |acc, e| Some(acc.max(e.max_len()?))),
+        }
+    }
+}
+
+/// Written back in the pattern syntax, with the ambiguity classes as IUPAC codes.
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Set(set) => write!(f, "{}", set.to_iupac().unwrap_or(b'-') as char),
+            Expr::Repeat { expr, min, max } => {
+                match **expr {
+                    Expr::Set(_) => write!(f, "{expr}")?,
+                    _ => write!(f, "({expr})")?,
+                }
+                match max {
+                    Some(max) if max == min => write!(f, "{min}"),
+                    Some(max) => write!(f, "{min}-{max}"),
+                    None => write!(f, "{min}-"),
+                }
+            }
+            Expr::Concat(v) => {
+                for (i, e) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, ",")?;
+                    }
+                    match e {
+                        Expr::Alt(_) => write!(f, "({e})")?,
+                        _ => write!(f, "{e}")?,
+                    }
+                }
+                Ok(())
+            }
+            Expr::Alt(v) => {
+                for (i, e) in v.iter().enumerate() {
+                    if i > 0 {
+                        write!(f, "|")?;
+                    }
+                    write!(f, "{e}")?;
+                }
+                Ok(())
+            }
+        }
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Pattern {
+    source: String,
+    expr: Expr,
+    forward: Dfa,
+    reverse: Dfa,
+    /// Pattern of the reverse strand, compiled the first time it's needed.
+    rc: OnceLock<Box<Pattern>>,
+}
+
+impl Pattern {
+    pub fn parse(source: &str) -> Result<Pattern, PatternError> {
+        let mut parser = Parser { src: source.as_bytes(), pos: 0 };
+        let expr = parser.alternation()?;
+        parser.skip_ws();
+        if let Some(c) = parser.peek() {
+            return Err(parser.error(PatternErrorKind::UnexpectedChar(c as char)));
+        }
+        if expr.nullable() {
+            return Err(PatternError { pos: 0, kind: PatternErrorKind::MatchesEmpty });
+        }
+        Pattern::compile(source.to_string(), expr)
+    }
+
+    fn compile(source: String, expr: Expr) -> Result<Pattern, PatternError> {
+        Ok(Pattern {
+            source,
+            forward: Dfa::forward(&[&expr])?,
+            reverse: Dfa::reverse(&[&expr])?,
+            expr,
+            rc: OnceLock::new(),
+        })
+    }
+
+    /// Pattern matching the reverse complement of what `self` matches: what it can match
+    /// in a sequence is what `self` can match on the reverse strand. Its searches go
+    /// leftmost-longest in the forward direction, though: `find_strand_iter` searches the
+    /// reverse strand itself.
+    pub fn reverse_complement(&self) -> &Pattern {
+        self.rc.get_or_init(|| {
+            let expr = self.expr.reverse_complement();
+            // same number of states as self, so it can't be too complex
+            Box::new(Pattern::compile(expr.to_string(), expr).expect("reverse complement of a valid pattern"))
+        })
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.source
+    }
+
+    pub fn expr(&self) -> &Expr {
+        &self.expr
+    }
+
+    /// Length in bytes of the longest possible match, `None` for open ranges.
+    pub fn max_len(&self) -> Option<usize> {
+        self.expr.max_len()
+    }
+
+    /// Leftmost-longest match starting at or after `start`, as a byte range of `s`.
+    pub fn find_at(&self, s: &str, start: usize) -> Option<(usize, usize)> {
+        Matches::new(self, s, start, false).next().map(|(i, m)| (i, i + m.len()))
+    }
+
+    /// First match in `s`: its position and the matched slice.
+    pub fn find<'a>(&self, s: &'a str) -> Option<(usize, &'a str)> {
+        self.find_iter(s).next()
+    }
+
+    /// All the non overlapping matches, the search goes on after the end of each match.
+    pub fn find_iter<'p, 's>(&'p self, s: &'s str) -> Matches<'p, 's> {
+        Matches::new(self, s, 0, false)
+    }
+
+    /// The longest match for every position where a match starts, even if it overlaps
+    /// the previous one.
+    pub fn find_overlapping_iter<'p, 's>(&'p self, s: &'s str) -> Matches<'p, 's> {
+        Matches::new(self, s, 0, true)
+    }
+
+    /// Hits on the forward strand, the reverse one or both, see `StrandMatches`.
+    pub fn find_strand_iter<'p, 's>(&'p self, s: &'s str, strand: Strand) -> StrandMatches<'p, 's> {
+        StrandMatches::new(self, s, strand)
+    }
+
+    /// End of the longest match starting exactly at `start`.
+    pub fn longest_at(&self, s: &str, start: usize) -> Option<usize> {
+        let mut state = self.forward.start();
+        let mut end = None;
+        for (i, &b) in s.as_bytes().iter().enumerate().skip(start) {
+            state = self.forward.next(state, b);
+            if state == DEAD {
+                break;
+            }
+            if self.forward.is_accept(state) {
+                end = Some(i + 1);
+            }
+        }
+        end
+    }
+
+    pub(crate) fn forward_dfa(&self) -> &Dfa {
+        &self.forward
+    }
+
+    pub(crate) fn reverse_dfa(&self) -> &Dfa {
+        &self.reverse
+    }
+}
+
+/// Patterns are equal if they have the same source: the automata follow from it, and
+/// whether the reverse complement has been compiled yet doesn't matter.
+impl PartialEq for Pattern {
+    fn eq(&self, other: &Pattern) -> bool {
+        self.source == other.source
+    }
+}
+
+impl Eq for Pattern {}
+
+impl FromStr for Pattern {
+    type Err = PatternError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Pattern::parse(s)
+    }
+}
+
+impl fmt::Display for Pattern {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.source)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum PatternErrorKind {
+    UnexpectedEnd,
+    UnexpectedChar(char),
+    EmptyClass,
+    UnclosedClass,
+    UnclosedGroup,
+    CountOverflow,
+    InvalidRange { min: u32, max: u32 },
+    MatchesEmpty,
+    /// The DFA of the pattern would have too many states.
+    TooComplex,
+}
+
+/// Error returned by `Pattern::parse`, `pos` is the byte offset in the pattern.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PatternError {
+    pub pos: usize,
+    pub kind: PatternErrorKind,
+}
+
+impl fmt::Display for PatternError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.kind {
+            PatternErrorKind::UnexpectedEnd => write!(f, "unexpected end of pattern"),
+            PatternErrorKind::UnexpectedChar(c) => write!(f, "unexpected {c:?} at {}", self.pos),
+            PatternErrorKind::EmptyClass => write!(f, "empty class at {}", self.pos),
+            PatternErrorKind::UnclosedClass => write!(f, "missing ']' for the class at {}", self.pos),
+            PatternErrorKind::UnclosedGroup => write!(f, "missing ')' for the group at {}", self.pos),
+            PatternErrorKind::CountOverflow => write!(f, "count too large at {}", self.pos),
+            PatternErrorKind::InvalidRange { min, max } => {
+                write!(f, "invalid range {min}-{max} at {}", self.pos)
+            }
+            PatternErrorKind::MatchesEmpty => write!(f, "pattern matches the empty sequence"),
+            PatternErrorKind::TooComplex => write!(f, "pattern too complex"),
+        }
+    }
+}
+
+impl Error for PatternError {}
+
+struct Parser<'a> {
+    src: &'a [u8],
+    pos: usize,
+}
+
+impl Parser<'_> {
+    fn error(&self, kind: PatternErrorKind) -> PatternError {
+        PatternError { pos: self.pos, kind }
+    }
+
+    fn skip_ws(&mut self) {
+        while self.src.get(self.pos).is_some_and(u8::is_ascii_whitespace) {
+            self.pos += 1;
+        }
+    }
+
+    fn peek(&mut self) -> Option<u8> {
+        self.skip_ws();
+        self.src.get(self.pos).copied()
+    }
+
+    fn eat(&mut self, c: u8) -> bool {
+        if self.peek() == Some(c) {
+            self.pos += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    fn alternation(&mut self) -> Result<Expr, PatternError> {
+        let mut alts = vec![self.concat()?];
+        while self.eat(b'|') {
+            alts.push(self.concat()?);
+        }
+        Ok(if alts.len() == 1 { alts.pop().unwrap() } else { Expr::Alt(alts) })
+    }
+
+    fn concat(&mut self) -> Result<Expr, PatternError> {
+        let mut items = vec![self.element()?];
+        while self.eat(b',') || self.peek().is_some_and(|c| matches!(c, b'[' | b'(') || BaseSet::from_iupac(c).is_some()) {
+            items.push(self.element()?);
+        }
+        Ok(if items.len() == 1 { items.pop().unwrap() } else { Expr::Concat(items) })
+    }
+
+    fn element(&mut self) -> Result<Expr, PatternError> {
+        let atom = self.atom()?;
+        let Some(min) = self.number()? else {
+            return Ok(atom);
+        };
+        let start = self.pos;
+        let max = if self.eat(b'-') { self.number()? } else { Some(min) };
+        if let Some(max) = max {
+            if max < min {
+                return Err(PatternError { pos: start, kind: PatternErrorKind::InvalidRange { min, max } });
+            }
+            if (min, max) == (1, 1) {
+                return Ok(atom);
+            }
+        }
+        Ok(Expr::Repeat { expr: Box::new(atom), min, max })
+    }
+
+    fn atom(&mut self) -> Result<Expr, PatternError> {
+        let start = self.pos;
+        match self.peek() {
+            None => Err(self.error(PatternErrorKind::UnexpectedEnd)),
+            Some(b'[') => {
+                self.pos += 1;
+                let mut set = BaseSet::NONE;
+                loop {
+                    match self.peek() {
+                        Some(b']') => break,
+                        Some(c) => match BaseSet::from_iupac(c) {
+                            Some(s) => {
+                                set = set.union(s);
+                                self.pos += 1;
+                            }
+                            None => return Err(self.error(PatternErrorKind::UnexpectedChar(c as char))),
+                        },
+                        None => return Err(PatternError { pos: start, kind: PatternErrorKind::UnclosedClass }),
+                    }
+                }
+                self.pos += 1;
+                if set.is_empty() {
+                    return Err(PatternError { pos: start, kind: PatternErrorKind::EmptyClass });
+                }
+                Ok(Expr::Set(set))
+            }
+            Some(b'(') => {
+                self.pos += 1;
+                let inner = self.alternation()?;
+                if !self.eat(b')') {
+                    return Err(PatternError { pos: start, kind: PatternErrorKind::UnclosedGroup });
+                }
+                Ok(inner)
+            }
+            Some(c) => match BaseSet::from_iupac(c) {
+                Some(s) => {
+                    self.pos += 1;
+                    Ok(Expr::Set(s))
+                }
+                None => Err(self.error(PatternErrorKind::UnexpectedChar(c as char))),
+            },
+        }
+    }
+
+    fn number(&mut self) -> Result<Option<u32>, PatternError> {
+        if !self.peek().is_some_and(|c| c.is_ascii_digit()) {
+            return Ok(None);
+        }
+        let start = self.pos;
+        let mut n: u32 = 0;
+        while let Some(c) = self.src.get(self.pos).filter(|c| c.is_ascii_digit()) {
+            n = n
+                .checked_mul(10)
+                .and_then(|n| n.checked_add((c - b'0') as u32))
+                .ok_or(PatternError { pos: start, kind: PatternErrorKind::CountOverflow })?;
+            self.pos += 1;
+        }
+        Ok(Some(n))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    // reference implementation: all the positions where a match starting at `pos` ends
+    fn ends(e: &Expr, s: &[u8], pos: usize) -> BTreeSet<usize> {
+        match e {
+            Expr::Set(set) => {
+                if pos < s.len() && set.matches(s[pos]) {
+                    BTreeSet::from([pos + 1])
+                } else {
+                    BTreeSet::new()
+                }
+            }
+            Expr::Repeat { expr, min, max } => {
+                let mut out = BTreeSet::new();
+                let mut current = BTreeSet::from([pos]);
+                let mut seen = BTreeSet::new();
+                let mut k = 0u32;
+                loop {
+                    if k >= *min {
+                        out.extend(current.iter().copied());
+                        current.retain(|p| seen.insert(*p));
+                    }
+                    if max.is_some_and(|m| k >= m) || current.is_empty() {
+                        break;
+                    }
+                    current = current.iter().flat_map(|p| ends(expr, s, *p)).collect();
+                    k += 1;
+                }
+                out
+            }
+            Expr::Concat(v) => v.iter().fold(BTreeSet::from([pos]), |acc, e| {
+                acc.iter().flat_map(|p| ends(e, s, *p)).collect()
+            }),
+            Expr::Alt(v) => v.iter().flat_map(|e| ends(e, s, pos)).collect(),
+        }
+    }
+
+    fn naive_iter(p: &Pattern, s: &str, overlapping: bool) -> Vec<(usize, usize)> {
+        let mut out = Vec::new();
+        let mut i = 0;
+        while i < s.len() {
+            match ends(p.expr(), s.as_bytes(), i).last() {
+                Some(&end) => {
+                    out.push((i, end));
+                    i = if overlapping { i + 1 } else { end };
+                }
+                None => i += 1,
+            }
+        }
+        out
+    }
+
+    // small deterministic generator, good enough for test inputs
+    fn random_dna(len: usize, mut seed: u64) -> String {
+        (0..len)
+            .map(|_| {
+                seed = seed.wrapping_mul(6364136223846793005).wrapping_add(1442695040888963407);
+                b"ACGTN"[((seed >> 33) % 5) as usize] as char
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dfa_agrees_with_reference() {
+        let patterns = ["A1-1,C2-4", "A1-3,C1-2", "R2-,Y", "[AG]1-3,A1-2", "(A,C)2-,G|T", "A1-,C|A", "N3", "(A|C,G)1-3,T2"];
+        for (k, p) in patterns.iter().enumerate() {
+            let pat = Pattern::parse(p).unwrap();
+            let s = random_dna(2000, k as u64);
+            for overlapping in [false, true] {
+                let found: Vec<_> = Matches::new(&pat, &s, 0, overlapping).map(|(i, m)| (i, i + m.len())).collect();
+                assert_eq!(found, naive_iter(&pat, &s, overlapping), "pattern {p}, overlapping {overlapping}");
+            }
+        }
+    }
+
+    #[test]
+    fn non_overlapping_and_overlapping() {
+        let pat = Pattern::parse("A1-2,C").unwrap();
+        let found: Vec<_> = pat.find_iter("AACAC").collect();
+        assert_eq!(found, vec![(0, "AAC"), (3, "AC")]);
+        let found: Vec<_> = pat.find_overlapping_iter("AACAC").collect();
+        assert_eq!(found, vec![(0, "AAC"), (1, "AC"), (3, "AC")]);
+    }
+
+    #[test]
+    fn find_at_and_longest_at() {
+        let pat = Pattern::parse("A1-2,C").unwrap();
+        assert_eq!(pat.find_at("AACAC", 1), Some((1, 3)));
+        assert_eq!(pat.find_at("AACAC", 3), Some((3, 5)));
+        assert_eq!(pat.find_at("AACAC", 4), None);
+        assert_eq!(pat.longest_at("AACAC", 0), Some(3));
+        assert_eq!(pat.longest_at("AACAC", 2), None);
+    }
+
+    #[test]
+    fn non_dna_bytes_never_match() {
+        let pat = Pattern::parse("N1-").unwrap();
+        let found: Vec<_> = pat.find_iter("AC\nGTè-A").collect();
+        assert_eq!(found, vec![(0, "AC"), (3, "GT"), (8, "A")]);
+    }
+
+    fn find<'a>(s: &'a str, p: &str) -> Option<(usize, &'a str)> {
+        Pattern::parse(p).unwrap().find(s)
+    }
+
+    #[test]
+    fn old_syntax() {
+        assert_eq!(find("AACCGGTAACC", "A1-1,C2-4"), Some((1, "ACC")));
+        assert_eq!(find("AACCGGTAACC", "G1-1,T1-4"), Some((5, "GT")));
+        // the greedy search of the old syntax found nothing here
+        assert_eq!(find("AAA", "A1-3,A1-1"), Some((0, "AAA")));
+    }
+
+    #[test]
+    fn multi_digit_and_open_ranges() {
+        let s = "CAAAAAAAAAAAAG";
+        assert_eq!(find(s, "A1-12"), Some((1, "AAAAAAAAAAAA")));
+        assert_eq!(find(s, "A12-,G"), Some((1, "AAAAAAAAAAAAG")));
+        assert_eq!(find(s, "A13-,G"), None);
+        assert_eq!(find(s, "C,A2-"), Some((0, "CAAAAAAAAAAAA")));
+    }
+
+    #[test]
+    fn literal_motifs() {
+        assert_eq!(find("TTACGTAA", "ACGT"), Some((2, "ACGT")));
+        assert_eq!(find("TTACCCGTAA", "AC3G"), Some((2, "ACCCG")));
+    }
+
+    #[test]
+    fn spaces_and_lowercase() {
+        assert_eq!(find("AACCGG", " a1-1 , c 2 - 4 "), Some((1, "ACC")));
+    }
+
+    #[test]
+    fn ambiguity_codes_and_classes() {
+        assert_eq!(find("TTAGGC", "R2-3"), Some((2, "AGG")));
+        assert_eq!(find("TTAGGC", "[AG]1-3,C"), Some((2, "AGGC")));
+        assert_eq!(find("TTAGGC", "N3"), Some((0, "TTA")));
+        assert_eq!(find("TTNGGC", "A"), None);
+        assert_eq!(find("TTNGGC", "[AG]"), Some((3, "G")));
+    }
+
+    #[test]
+    fn alternation_prefers_longest() {
+        assert_eq!(find("GGACCC", "A|A,C2-"), Some((2, "ACCC")));
+        assert_eq!(find("GGTCCC", "(A|T),C1-2"), Some((2, "TCC")));
+        assert_eq!(find("ACACACG", "(A,C)2-,G"), Some((0, "ACACACG")));
+    }
+
+    #[test]
+    fn errors() {
+        let kind = |p: &str| Pattern::parse(p).unwrap_err().kind;
+        assert_eq!(kind(""), PatternErrorKind::UnexpectedEnd);
+        assert_eq!(kind("A1-1,"), PatternErrorKind::UnexpectedEnd);
+        assert_eq!(kind("X1-2"), PatternErrorKind::UnexpectedChar('X'));
+        assert_eq!(kind("A4-2"), PatternErrorKind::InvalidRange { min: 4, max: 2 });
+        assert_eq!(kind("A99999999999"), PatternErrorKind::CountOverflow);
+        assert_eq!(kind("[]"), PatternErrorKind::EmptyClass);
+        assert_eq!(kind("[AC"), PatternErrorKind::UnclosedClass);
+        assert_eq!(kind("(A|C"), PatternErrorKind::UnclosedGroup);
+        assert_eq!(kind("A0-3"), PatternErrorKind::MatchesEmpty);
+        assert_eq!(kind("A1-2)"), PatternErrorKind::UnexpectedChar(')'));
+    }
+
+    #[test]
+    fn iupac_round_trip_and_complement() {
+        for c in b"ACGTRYSWKMBDHVN" {
+            let set = BaseSet::from_iupac(*c).unwrap();
+            assert_eq!(set.to_iupac(), Some(*c));
+            assert_eq!(set.complement().complement(), set);
+        }
+        let comp = |c: u8| BaseSet::from_iupac(c).unwrap().complement().to_iupac().unwrap();
+        assert_eq!(b"ACGTRYSWKMBDHVN".map(comp), *b"TGCAYRSWMKVHDBN");
+    }
+
+    #[test]
+    fn display_round_trip() {
+        for p in ["A,C2-4", "[AG]1-3,C", "(A|C,G)2-,T", "A|(C,G)3", "N12-"] {
+            let expr = Pattern::parse(p).unwrap().expr().clone();
+            assert_eq!(Pattern::parse(&expr.to_string()).unwrap().expr(), &expr, "{p}");
+        }
+        assert_eq!(Pattern::parse("[AG]1-3,C").unwrap().expr().to_string(), "R1-3,C");
+    }
+
+    #[test]
+    fn reverse_complement_pattern() {
+        let pat = Pattern::parse("A1-1,C2-4,[AG]").unwrap();
+        assert_eq!(pat.reverse_complement().as_str(), "Y,G2-4,T");
+        assert_eq!(pat.reverse_complement().reverse_complement().expr(), pat.expr());
+    }
+
+    #[test]
+    fn equality_ignores_the_reverse_complement_cache() {
+        let (a, b) = (Pattern::parse("ACGT").unwrap(), Pattern::parse("ACGT").unwrap());
+        assert_eq!(a, b);
+        a.reverse_complement();
+        assert_eq!(a, b);
+        assert_eq!(a.clone(), b);
+        assert_ne!(a, Pattern::parse("ACGA").unwrap());
+    }
+
+    #[test]
+    fn max_len() {
+        assert_eq!(Pattern::parse("A1-1,C2-4").unwrap().max_len(), Some(5));
+        assert_eq!(Pattern::parse("A|(C,G)3").unwrap().max_len(), Some(6));
+        assert_eq!(Pattern::parse("A2-").unwrap().max_len(), None);
+    }
+}
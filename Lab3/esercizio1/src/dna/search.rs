@@ -0,0 +1,143 @@
+use std::collections::HashSet;
+
+use super::automaton::{Dfa, DEAD};
+use super::pattern::Pattern;
+
+/// Scanning state shared by the single and the multi pattern iterators.
+///
+/// One backward pass with the reversed DFA marks every position where a match starts,
+/// then the anchored DFA extends each start to its longest match. When the search jumps
+/// to the end of each match, the states visited past that end (that can't lead to a
+/// longer match) are remembered, so no part of the sequence is scanned more than once
+/// per DFA state even with open ranges.
+pub(crate) struct Scanner<'d, 's> {
+    forward: &'d Dfa,
+    pub(crate) s: &'s str,
+    starts: Vec<u64>,
+    pub(crate) pos: usize,
+    dead: HashSet<(u32, usize)>,
+}
+
+impl<'d, 's> Scanner<'d, 's> {
+    pub(crate) fn new(forward: &'d Dfa, reverse: &Dfa, s: &'s str, from: usize) -> Self {
+        let bytes = s.as_bytes();
+        let mut starts = vec![0u64; bytes.len().div_ceil(64)];
+        let mut state = reverse.start();
+        for i in (from..bytes.len()).rev() {
+            state = reverse.next(state, bytes[i]);
+            if reverse.is_accept(state) {
+                starts[i / 64] |= 1 << (i % 64);
+            }
+        }
+        Scanner { forward, s, starts, pos: from, dead: HashSet::new() }
+    }
+
+    /// First position >= `self.pos` where a match starts.
+    pub(crate) fn next_start(&self) -> Option<usize> {
+        let mut word = self.pos / 64;
+        if word >= self.starts.len() {
+            return None;
+        }
+        let mut bits = self.starts[word] & (!0u64 << (self.pos % 64));
+        loop {
+            if bits != 0 {
+                return Some(word * 64 + bits.trailing_zeros() as usize);
+            }
+            word += 1;
+            bits = *self.starts.get(word)?;
+        }
+    }
+
+    /// Longest match starting at `start` among all the patterns of the DFA: its end and
+    /// the lowest index of the patterns that end there. Remembers the dead states, so it
+    /// must be followed by a jump of `pos` to the returned end (see `advance`).
+    pub(crate) fn longest(&mut self, start: usize) -> Option<(usize, u32)> {
+        let mut state = self.forward.start();
+        let mut best = None;
+        let mut trail = Vec::new();
+        for (i, &b) in self.s.as_bytes().iter().enumerate().skip(start) {
+            state = self.forward.next(state, b);
+            if state == DEAD || self.dead.contains(&(state, i + 1)) {
+                break;
+            }
+            match self.forward.accepts(state).first() {
+                Some(&id) => {
+                    best = Some((i + 1, id));
+                    trail.clear();
+                }
+                None => trail.push((state, i + 1)),
+            }
+        }
+        self.dead.extend(trail);
+        best
+    }
+
+    /// Calls `f(pattern, end)` with the longest match from `start` of every pattern that
+    /// matches there, in increasing pattern order. `last` is scratch space with one
+    /// `usize::MAX` entry per pattern and is left as it was.
+    pub(crate) fn longest_each(&self, start: usize, last: &mut [usize], mut f: impl FnMut(u32, usize)) {
+        let mut state = self.forward.start();
+        let mut touched = Vec::new();
+        for (i, &b) in self.s.as_bytes().iter().enumerate().skip(start) {
+            state = self.forward.next(state, b);
+            if state == DEAD {
+                break;
+            }
+            for &id in self.forward.accepts(state) {
+                if last[id as usize] == usize::MAX {
+                    touched.push(id);
+                }
+                last[id as usize] = i + 1;
+            }
+        }
+        touched.sort_unstable();
+        for id in touched {
+            f(id, std::mem::replace(&mut last[id as usize], usize::MAX));
+        }
+    }
+
+    pub(crate) fn advance(&mut self, pos: usize) {
+        self.pos = pos;
+        if !self.dead.is_empty() {
+            self.dead.retain(|&(_, p)| p > pos);
+        }
+    }
+}
+
+/// Iterator over the matches of a `Pattern`, yields the position and the matched slice.
+///
+/// Matches are found in O(n) for a given pattern, see `Scanner`. The overlapping mode
+/// reports the longest match for every start position and costs O(n * L), where L is the
+/// length of the longest match.
+pub struct Matches<'p, 's> {
+    scanner: Scanner<'p, 's>,
+    overlapping: bool,
+}
+
+impl<'p, 's> Matches<'p, 's> {
+    pub(crate) fn new(pat: &'p Pattern, s: &'s str, from: usize, overlapping: bool) -> Self {
+        Matches {
+            scanner: Scanner::new(pat.forward_dfa(), pat.reverse_dfa(), s, from),
+            overlapping,
+        }
+    }
+}
+
+impl<'s> Iterator for Matches<'_, 's> {
+    type Item = (usize, &'s str);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let start = self.scanner.next_start()?;
+        let end = if self.overlapping {
+            let mut end = None;
+            self.scanner.longest_each(start, &mut [usize::MAX], |_, e| end = Some(e));
+            self.scanner.advance(start + 1);
+            end?
+        } else {
+            let (end, _) = self.scanner.longest(start)?;
+            self.scanner.advance(end);
+            end
+        };
+        Some((start, &self.scanner.s[start..end]))
+    }
+}
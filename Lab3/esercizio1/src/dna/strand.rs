@@ -0,0 +1,129 @@
+use std::iter::Peekable;
+
+use super::pattern::{BaseSet, Pattern};
+use super::search::Matches;
+
+/// Strand of the sequence to search. The hits of a search are tagged with `Forward` or
+/// `Reverse`, never with `Both`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, Default)]
+pub enum Strand {
+    #[default]
+    Forward,
+    Reverse,
+    Both,
+}
+
+/// Complement of a nucleotide or IUPAC code, keeping the case; any other byte is returned
+/// as it is.
+pub fn complement(b: u8) -> u8 {
+    match BaseSet::from_iupac(b).and_then(|s| s.complement().to_iupac()) {
+        Some(c) if b.is_ascii_lowercase() => c.to_ascii_lowercase(),
+        Some(c) => c,
+        None => b,
+    }
+}
+
+/// The sequence of the other strand, read in its 5' -> 3' direction. Characters that
+/// aren't ASCII are reversed with the others and kept as they are.
+pub fn reverse_complement(s: &str) -> String {
+    s.chars().rev().map(|c| if c.is_ascii() { complement(c as u8) as char } else { c }).collect()
+}
+
+/// Iterator over the hits of a pattern on one or both strands, ordered by position.
+///
+/// The hits on the reverse strand are the ones a search of the reverse complement of the
+/// sequence finds: leftmost-longest in the direction of that strand, so they don't
+/// overlap each other. Their position and slice are mapped back to forward coordinates,
+/// the sequence actually bound is the reverse complement of the slice. They come in the
+/// opposite order of the forward ones, so they're all found up front.
+pub struct StrandMatches<'p, 's> {
+    forward: Option<Peekable<Matches<'p, 's>>>,
+    reverse: Peekable<std::vec::IntoIter<(usize, &'s str)>>,
+}
+
+impl<'p, 's> StrandMatches<'p, 's> {
+    pub(crate) fn new(pat: &'p Pattern, s: &'s str, strand: Strand) -> Self {
+        let fwd = matches!(strand, Strand::Forward | Strand::Both);
+        let rev = matches!(strand, Strand::Reverse | Strand::Both);
+        let mut reverse = Vec::new();
+        if rev {
+            // reverse_complement keeps the length of every character: a match at j in the
+            // other strand covers the same characters as s[n - j - len..n - j]
+            let other = reverse_complement(s);
+            let n = s.len();
+            reverse = pat.find_iter(&other).map(|(j, m)| (n - j - m.len(), &s[n - j - m.len()..n - j])).collect();
+            reverse.reverse();
+        }
+        StrandMatches { forward: fwd.then(|| pat.find_iter(s).peekable()), reverse: reverse.into_iter().peekable() }
+    }
+}
+
+impl<'s> Iterator for StrandMatches<'_, 's> {
+    type Item = (usize, &'s str, Strand);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let f = self.forward.as_mut().and_then(|m| m.peek()).map(|(i, _)| *i);
+        let r = self.reverse.peek().map(|(i, _)| *i);
+        let ((i, m), strand) = match (f, r) {
+            (Some(f), Some(r)) if r < f => (self.reverse.next()?, Strand::Reverse),
+            (Some(_), _) => (self.forward.as_mut()?.next()?, Strand::Forward),
+            (None, Some(_)) => (self.reverse.next()?, Strand::Reverse),
+            (None, None) => return None,
+        };
+        Some((i, m, strand))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn reverse_complement_iupac() {
+        assert_eq!(reverse_complement("AACGTN"), "NACGTT");
+        assert_eq!(reverse_complement("ARYacg"), "cgtRYT");
+        assert_eq!(reverse_complement("AC-GU"), "AC-GT");
+        assert_eq!(reverse_complement("é"), "é");
+        assert_eq!(reverse_complement("Aé→C"), "G→éT");
+    }
+
+    #[test]
+    fn both_strands_in_forward_coordinates() {
+        let pat = Pattern::parse("A1-1,C2-4").unwrap();
+        // GGT is the reverse complement of ACC
+        let s = "AACCGGTTT";
+        let hits: Vec<_> = pat.find_strand_iter(s, Strand::Both).collect();
+        assert_eq!(hits, vec![(1, "ACC", Strand::Forward), (4, "GGT", Strand::Reverse)]);
+        let hits: Vec<_> = pat.find_strand_iter(s, Strand::Reverse).collect();
+        assert_eq!(hits, vec![(4, "GGT", Strand::Reverse)]);
+        for (i, m, _) in hits {
+            let on_reverse = reverse_complement(s);
+            let j = s.len() - i - m.len();
+            assert_eq!(pat.find_at(&on_reverse, j), Some((j, j + m.len())));
+        }
+    }
+
+    #[test]
+    fn reverse_hits_are_those_of_the_other_strand() {
+        // on the other strand AAA, A2 is at 0: TT at 1 here, not at 0
+        let pat = Pattern::parse("A2").unwrap();
+        let hits: Vec<_> = pat.find_strand_iter("TTT", Strand::Reverse).collect();
+        assert_eq!(hits, vec![(1, "TT", Strand::Reverse)]);
+        for (s, p) in [("TTTTTGTT", "A2-3"), ("ACGTTGCAAC", "(GT|A)C"), ("CCGGGé", "C,C1-2"), ("ATATAT", "AT,A")] {
+            let pat = Pattern::parse(p).unwrap();
+            let other = reverse_complement(s);
+            let mut expected: Vec<_> = pat.find_iter(&other).map(|(j, m)| (s.len() - j - m.len(), reverse_complement(m))).collect();
+            expected.reverse();
+            let hits: Vec<_> = pat.find_strand_iter(s, Strand::Reverse).map(|(i, m, _)| (i, m.to_string())).collect();
+            assert_eq!(hits, expected, "{} in {}", p, s);
+        }
+    }
+
+    #[test]
+    fn palindromic_sites_hit_both_strands() {
+        // EcoRI
+        let pat = Pattern::parse("GAATTC").unwrap();
+        let hits: Vec<_> = pat.find_strand_iter("TTGAATTCAA", Strand::Both).collect();
+        assert_eq!(hits, vec![(2, "GAATTC", Strand::Forward), (2, "GAATTC", Strand::Reverse)]);
+    }
+}
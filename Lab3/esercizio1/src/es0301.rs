@@ -1,3 +1,7 @@
+use esercizio1::dna::edit::{edit_matches, matches_mut};
+use esercizio1::dna::io::FastaReader;
+use esercizio1::dna::{find_approx, find_approx_edits, par_subsequences, Matches, MatchKind, MultiPatternSearcher, Pattern, Strand, StrandMatches};
+
 // find all subsequences of seq in s and return a vector of tuples containing the start position
 // and the found subsequences as string slices
 // ignore overlaps: if a subsequence is found, the search must continue from the next character
@@ -7,60 +11,22 @@
 
 
 
-pub fn find_sub<'a,'b>(s: &'a str, seq: &'b str) -> Option<(usize, &'a str) > {
-    let enc_seq: Vec<(char,u32,u32)> = seq.split(',')
-                .map(|subs| 
-                    {let subs = subs.replace("-","");
-                    let mut iter = subs.chars();
-                    (iter.next().unwrap(),iter.next().unwrap().to_digit(10).unwrap(),iter.next().unwrap().to_digit(10).unwrap())
-                    }
-                )
-                .collect();
-    for (i,_) in s.chars().enumerate(){
-        let mut s2 = &s[i..];
-        let mut final_idx: usize = i;
-        let mut ok = false;
-        for (c,min,max) in &enc_seq {
-            let len = s2.strip_suffix(s2.trim_start_matches(*c)).unwrap().chars().count() as u32;
-            if len >= *min{
-                final_idx += if len < *max {len} else {*max} as usize;
-                s2 = &s[final_idx..];
-            }
-            else{
-                // to skip at the next char
-                ok = true;
-                break;
-            }
-        }
-        if ok{
-            continue;
-        }
-        return Some((i,&s[i..final_idx]));
-    }
-    None
-    
-}
+// the pattern is parsed once with Pattern::parse (see dna::pattern for the syntax) and
+// then reused for every search, instead of decoding seq at each call; Pattern::find
+// takes the place of find_sub and Pattern::find_iter scans s only once for all the matches
 
 
-fn subsequences1<'a,'b>(s: &'a str, seq : &'b str) -> Vec<(usize, &'a str)> {
-    let mut subseqs: Vec<(usize, &'a str)> = Vec::new();
-    let mut last_idx = 0;
-    while last_idx < s.len() {
-        let res = find_sub(&s[last_idx..], seq);
-        match res {
-            Some((i,subs)) => {subseqs.push((i + last_idx,subs)); last_idx += i + subs.len();}
-            None => {last_idx += 1;}
-        }
-    }
-    subseqs
+fn subsequences1<'a>(s: &'a str, pat: &Pattern) -> Vec<(usize, &'a str)> {
+    // a single linear scan: restarting find_sub after each match would rescan the rest of s every time
+    pat.find_iter(s).collect()
 }
 
 
 pub fn demo1() {
     let a = "AACCGGTAACC".to_string();
-    let seq = "A1-1,C2-4";
+    let pat = Pattern::parse("A1-1,C2-4").unwrap();
 
-    for (off, sub) in subsequences1(&a, seq) {
+    for (off, sub) in subsequences1(&a, &pat) {
         println!("Found subsequence at position {}: {}", off, sub);
     }
 }
@@ -72,24 +38,21 @@ pub fn demo1() {
 // Now the string slices in the rsult depend from two input parameters, which ones?
 
 
-fn subsequences2<'a>(s: &'a str, seqs: &'a [&'a str]) -> Vec<(usize, &'a str, &'a str)> {
-    let mut subseqs2: Vec<(usize, &'a str, &'a str)> = Vec::new();
-    for seq in seqs {
-        let res = subsequences1(s, seq);
-        if !res.is_empty() {
-            for (i,slice) in res {
-                subseqs2.push((i,slice,seq));
-            }
-        }
-    }
-    subseqs2
+// a single automaton over all the patterns, so s is scanned once and not once per pattern;
+// PerPattern keeps the matches of each pattern non overlapping, as calling subsequences1 for each one
+fn subsequences2<'a>(s: &'a str, searcher: &'a MultiPatternSearcher) -> Vec<(usize, &'a str, &'a str)> {
+    searcher
+        .find_iter(s, MatchKind::PerPattern)
+        .map(|(i, slice, id)| (i, slice, searcher.patterns()[id].as_str()))
+        .collect()
 }
 
 pub fn demo2() {
     let a = "AACCGGTAACC".to_string();
-    let seqs = ["A1-1,C2-4", "G1-1,T1-4"];
+    let pats = ["A1-1,C2-4", "G1-1,T1-4"].map(|p| Pattern::parse(p).unwrap());
+    let searcher = MultiPatternSearcher::new(pats).unwrap();
 
-    for (off, matched, sub) in subsequences2(&a, &seqs) {
+    for (off, matched, sub) in subsequences2(&a, &searcher) {
         println!("Found subsequence {} at position {}: {}", matched, off, sub);
     }
 }
@@ -102,28 +65,25 @@ pub fn demo2() {
 // 4. Spoiler: basically it's not possibile to return more then one mutable reference to the same data
 // 5. Try this workaround: return a vector of indexes (first solution) and let the caller extract the mutable references
 // 7. (later in the course you will learn about smart pointers, which can be used to solve this kind of problems in a more elegant way)
-fn subsequences3(s: &str, seq: &str) -> Vec<(usize, usize)> {
-    let mut subseqs: Vec<(usize, usize)> = Vec::new();
-    let mut last_idx = 0;
-    while last_idx < s.len() {
-        let res = find_sub(&s[last_idx..], seq);
-        match res {
-            Some((i,subs)) => {subseqs.push((i + last_idx, i + last_idx + subs.len())); last_idx += i + subs.len();}
-            None => {last_idx += 1;}
-        }
-    }
-    subseqs
+// the mutable references to the same data can be more than one if they don't overlap: splitting
+// the string with split_at_mut at the boundaries of the matches gives disjoint &mut str (see dna::edit)
+fn subsequences3<'a>(s: &'a mut str, pat: &Pattern) -> Vec<(usize, &'a mut str)> {
+    matches_mut(s, pat)
 }
 
 pub fn demo3() {
     let mut a = "AACCGGTAACC".to_string();
-    let seq = "A1-1,C1-4";
+    let pat = Pattern::parse("A1-1,C1-4").unwrap();
 
-    for (off, end_off) in subsequences3(&a, seq) {
-        println!("Found subsequence at position: {} to {}", off, end_off);
-        let mut b = &mut a[off..end_off];
-        println!("Mutable ref to: {}", b);
+    for (off, sub) in subsequences3(&mut a, &pat) {
+        println!("Found subsequence at position {}: {}", off, sub);
+        sub.make_ascii_lowercase();
     }
+    println!("Soft masked: {}", a);
+
+    // edits that change the length of the string
+    let n = edit_matches(&mut a, &Pattern::parse("G2-").unwrap(), |e| e.replace("TTTT"));
+    println!("Replaced {} subsequences: {}", n, a);
 }
 
 
@@ -133,22 +93,17 @@ pub fn demo3() {
 // do you need to put lifetime annotations in the closure? why?
 
 // i don't need lifetime in the closure because doesn't have any return, so the variable don't live after the function
-fn subsequence4<F>(s: &str, seq: &str , mut f: F) where F: FnMut(usize,&str) {
-    let mut last_idx = 0;
-    while last_idx < s.len() {
-        let res = find_sub(&s[last_idx..], seq);
-        match res {
-            Some((i,subs)) => {f(i + last_idx,subs); last_idx += i + subs.len();}
-            None => {last_idx += 1;}
-        }
+fn subsequence4<F>(s: &str, pat: &Pattern, mut f: F) where F: FnMut(usize,&str) {
+    for (i, subs) in pat.find_iter(s) {
+        f(i, subs);
     }
 }
 
 pub fn demo4() {
     let a = "AACCGGTAACC".to_string();
-    let seq = "A1-1,C2-4";
+    let pat = Pattern::parse("A1-1,C2-4").unwrap();
 
-    subsequence4(&a, seq, |pos, sub| {
+    subsequence4(&a, &pat, |pos, sub| {
         println!("Found subsequence at position {}: {}", pos, sub);
     });
 }
@@ -162,30 +117,24 @@ pub fn demo4() {
 // This approach is similar to the previous one, but it's more flexible and it can be used in more complex scenarios. For example you may interrupt it
 // at any time and resume it later
 
+// the state (the last position found) is kept by the Matches iterator of the pattern
 struct SimpleDNAIter<'a,'b> {
-    s: &'a str,
-    seq: &'b str,
-    idx: usize,
+    matches: Matches<'b,'a>,
 }
 
 impl SimpleDNAIter<'_,'_>{
-    pub fn new<'a,'b>(s: &'a str, seq: &'b str) -> SimpleDNAIter<'a,'b> {
-        SimpleDNAIter { s: s, seq: seq, idx: 0 }
+    pub fn new<'a,'b>(s: &'a str, pat: &'b Pattern) -> SimpleDNAIter<'a,'b> {
+        SimpleDNAIter { matches: pat.find_iter(s) }
     }
 
     pub fn next(&mut self) -> Option<(usize, &str)> {
-        let res = find_sub(&self.s[self.idx..], self.seq);
-        if res.is_some() {
-            let (i,slice) = res.unwrap();
-            self.idx += i + slice.len();
-            return Some((self.idx - slice.len(), slice));
-        }
-        None
+        self.matches.next()
     }
 }
 
-pub fn demo_SimpleDNAIter() {
-    let mut dna_iter = SimpleDNAIter::new("ACGTACGTACGTACGT", "A1-1,C1-1");
+pub fn demo_simple_dna_iter() {
+    let pat = Pattern::parse("A1-1,C1-1").unwrap();
+    let mut dna_iter = SimpleDNAIter::new("ACGTACGTACGTACGT", &pat);
 
     while let Some((pos, subseq)) = dna_iter.next() {
         println!("Found subsequence at position {}: {}", pos, subseq);
@@ -196,48 +145,50 @@ pub fn demo_SimpleDNAIter() {
 
 // finally we want to implement a real iterator, so that it can be used in a for loop and it may be combined we all the most common iterator methods
 // The struct DNAIter is already defined, you have to implement the Iterator trait for it and add lifetimes
+// the strand option searches also (or only) the reverse strand: the positions are always in forward coordinates
+// and each hit tells on which strand it was found
 struct DNAIter<'a,'b> {
-    s: &'a str,
-    seq: &'b str,
-    idx: usize
+    matches: StrandMatches<'b,'a>,
 }
 
 impl DNAIter<'_,'_>{
-    pub fn new<'a,'b>(s: &'a str, seq: &'b str) -> DNAIter<'a,'b>{
+    pub fn new<'a,'b>(s: &'a str, pat: &'b Pattern) -> DNAIter<'a,'b>{
+        DNAIter::with_strand(s, pat, Strand::Forward)
+    }
+
+    pub fn with_strand<'a,'b>(s: &'a str, pat: &'b Pattern, strand: Strand) -> DNAIter<'a,'b>{
         DNAIter {
-            s: s,
-            seq: seq,
-            idx: 0
+            matches: pat.find_strand_iter(s, strand),
         }
     }
 }
 
 impl <'a>Iterator for DNAIter<'a, '_>{
-    type Item = (usize, &'a str);
+    type Item = (usize, &'a str, Strand);
 
     fn next(&mut self) -> Option<Self::Item> {
-        let res = find_sub(&self.s[self.idx..], self.seq);
-        if res.is_some() {
-            let (i,slice) = res.unwrap();
-            self.idx += i + slice.len();
-            return Some((self.idx - slice.len(), slice));
-        }
-        None
+        self.matches.next()
     }
 }
 
 pub fn demo_dna_iter() {
-    let dna_iter = DNAIter::new("ACGTACGTAAACCCGTACGT", "A1-3,C1-2");
+    let pat = Pattern::parse("A1-3,C1-2").unwrap();
+    let dna_iter = DNAIter::new("ACGTACGTAAACCCGTACGT", &pat);
 
     // now you can combine it with all the iterator modifiers!!!
     dna_iter
-        .filter(|(pos, sub)| sub.len() >= 5)
-        .for_each(|(pos, sub)| {
+        .filter(|(_, sub, _)| sub.len() >= 5)
+        .for_each(|(pos, sub, _)| {
             println!(
                 "Found subsequence at least long 5 at position {}: {}",
                 pos, sub
             )
         });
+
+    DNAIter::with_strand("ACGTACGTAAACCCGTACGT", &pat, Strand::Both)
+        .for_each(|(pos, sub, strand)| {
+            println!("Found subsequence on the {:?} strand at position {}: {}", strand, pos, sub)
+        });
 }
 
 
@@ -245,26 +196,66 @@ pub fn demo_dna_iter() {
 // now let's return an iterator without defining a struct, just using a closure
 // the std lib of rust support you with the std::from_fn() function
 // we supply a skeleton implementation, you have to fill the closure
-fn subsequence5_iter<'a,'b>(s: &'a str, seq: &'b str) -> impl Iterator<Item = (usize, &'a str)> where 'b: 'a{
-    let mut pos = 0;
+fn subsequence5_iter<'a,'b>(s: &'a str, pat: &'b Pattern, strand: Strand) -> impl Iterator<Item = (usize, &'a str, Strand)> + use<'a, 'b> {
+    let mut matches = pat.find_strand_iter(s, strand);
     // and any other necessary variable to remember the state
-    std::iter::from_fn(move || {
-        if let Some((i, slice)) = find_sub(&s[pos..], seq) {
-            pos += i + slice.len();
-            return  Some((pos - slice.len(), slice));
-        } else {
-            None
-        }
-    })
+    std::iter::from_fn(move || matches.next())
 }
 
 pub fn demo_dna_iter2() {
-    subsequence5_iter("ACGTACGTAAACCGTACGT", "A1-3,C1-2")
-        .filter(|(pos, sub)| sub.len() >= 5)
-        .for_each(|(pos, sub)| {
+    let pat = Pattern::parse("A1-3,C1-2").unwrap();
+    subsequence5_iter("ACGTACGTAAACCGTACGT", &pat, Strand::Both)
+        .filter(|(_, sub, _)| sub.len() >= 5)
+        .for_each(|(pos, sub, strand)| {
             println!(
-                "Found subsequence at least long 5 at position {}: {}",
-                pos, sub
+                "Found subsequence at least long 5 on the {:?} strand at position {}: {}",
+                strand, pos, sub
             )
         });
-}
\ No newline at end of file
+}
+
+// long sequences can be split among threads: par_subsequences cuts s in overlapping chunks,
+// searches them with thread::scope and merges the results, equal to the ones of subsequences1
+pub fn demo_par() {
+    let a = "AACCGGTAACC".repeat(1000);
+    let pat = Pattern::parse("A1-1,C2-4").unwrap();
+
+    let found = par_subsequences(&a, &pat, 4);
+    assert_eq!(found, subsequences1(&a, &pat));
+    println!("Found {} subsequences with 4 threads, the last at position {}", found.len(), found.last().unwrap().0);
+}
+
+
+// reads carry sequencing errors: a primer is still found with a few mismatches,
+// or with a few edits when bases are missing or added
+pub fn demo_approx() {
+    let a = "TTTGATTACATTGACTACATTGATACATT";
+    let pat = Pattern::parse("GATTACA").unwrap();
+
+    for (pos, sub, d) in find_approx(a, &pat, 1).unwrap() {
+        println!("found {} at position {} with {} mismatches", sub, pos, d);
+    }
+    for (pos, sub, d) in find_approx_edits(a, &pat, 1).unwrap() {
+        println!("found {} at position {} with {} edits", sub, pos, d);
+    }
+}
+
+
+// real sequences come from FASTA/FASTQ files, with many records and wrapped lines:
+// the readers in dna::io join the lines of each record, so a match may cross a line break,
+// and the positions are relative to the record
+pub fn demo_fasta() {
+    let fasta = ">seq1 wrapped\nACGTACGTAA\nACCCGTACGT\n>seq2\nTTAACC\nGGAC\n";
+    let pat = Pattern::parse("A1-3,C1-2").unwrap();
+
+    for record in FastaReader::new(fasta.as_bytes()) {
+        match record {
+            Ok(record) => {
+                for (pos, sub) in record.find_iter(&pat) {
+                    println!("{}: found subsequence at position {}: {}", record.id, pos, sub);
+                }
+            }
+            Err(e) => println!("invalid FASTA: {}", e),
+        }
+    }
+}
@@ -10,9 +10,15 @@ fn main() {
     println!("====\nDEMO 4\n=====");
     es0301::demo4();
     println!("====\nSimplerDNAIter DEMO \n=====");
-    es0301::demo_SimpleDNAIter();
+    es0301::demo_simple_dna_iter();
     println!("====\ndna_iter DEMO \n=====");
     es0301::demo_dna_iter();
     println!("====\ndna_iter2 DEMO \n=====");
     es0301::demo_dna_iter2();
+    println!("====\nparallel DEMO \n=====");
+    es0301::demo_par();
+    println!("====\napproximate DEMO \n=====");
+    es0301::demo_approx();
+    println!("====\nFASTA DEMO \n=====");
+    es0301::demo_fasta();
 }
@@ -0,0 +1,79 @@
+// cargo bench --bench dlist
+//
+// The Rc<RefCell<..>> DList of List3 against the raw pointer one of List4, on the
+// operations of an LRU list: pushes and pops at the ends, full traversals and
+// removals in the middle through a cursor.
+
+use std::hint::black_box;
+use std::time::{Duration, Instant};
+
+use es1::es0401::{List3, List4};
+
+const N: usize = 1_000_000;
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let n = black_box(f());
+    (start.elapsed(), n)
+}
+
+fn row(name: &str, rc: (Duration, usize), raw: (Duration, usize)) {
+    assert_eq!(rc.1, raw.1, "{name}: different results");
+    let speedup = rc.0.as_secs_f64() / raw.0.as_secs_f64();
+    println!("{:<28} {:>12.2?} {:>12.2?} {:>8.1}x", name, rc.0, raw.0, speedup);
+}
+
+// the same code for the two lists, which have the same API
+macro_rules! bench {
+    ($list:ty) => {{
+        let push = time(|| {
+            let mut l = <$list>::new();
+            for i in 0..N {
+                if i % 2 == 0 { l.push_back(i) } else { l.push_front(i) }
+            }
+            l.len()
+        });
+        let l: $list = (0..N).collect();
+        let iter = time(|| l.iter().sum::<usize>());
+        let iter_rev = time(|| l.iter().rev().count());
+        let mut l: $list = (0..N).collect();
+        let pop = time(|| {
+            let mut n = 0;
+            while let (Some(_), Some(_)) = (l.pop_front(), l.pop_back()) {
+                n += 2;
+            }
+            n
+        });
+        let mut l: $list = (0..N).collect();
+        // walk the list once, removing every third element and inserting after every fifth
+        let cursor = time(|| {
+            let mut c = l.cursor_front_mut();
+            let mut i = 0;
+            while c.index().is_some() {
+                if i % 3 == 0 {
+                    c.remove_current();
+                } else {
+                    if i % 5 == 0 {
+                        c.insert_after(i);
+                    }
+                    c.move_next();
+                }
+                i += 1;
+            }
+            l.len()
+        });
+        [push, iter, iter_rev, pop, cursor]
+    }};
+}
+
+fn main() {
+    let rc = bench!(List3::DList<usize>);
+    let raw = bench!(List4::DList<usize>);
+
+    println!("{} elements", N);
+    println!("{:<28} {:>12} {:>12} {:>9}", "", "Rc<RefCell>", "NonNull", "speedup");
+    let names = ["push at both ends", "iter (sum)", "iter rev (count)", "pop at both ends", "cursor remove/insert"];
+    for (name, (a, b)) in names.iter().zip(rc.into_iter().zip(raw)) {
+        row(name, a, b);
+    }
+}
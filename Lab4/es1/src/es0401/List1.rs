@@ -0,0 +1,331 @@
+#[derive(Debug)]
+pub enum ListLink<T> {
+    Cons(T, Box<ListLink<T>>),
+    Nil,
+}
+#[derive(Debug)]
+pub struct List<T> {
+    head: ListLink<T>,
+}
+
+
+impl<T> List<T> {
+    pub fn new() -> Self {
+        // create a empty ListLink
+        Self { head: ListLink::Nil } 
+    }
+
+    // insert a new element at the beginning of the list
+    // you may encouter a problem with the borrow checker while trying to move self.head to a new variable
+    // why? look at mem::replace for solving it
+    pub fn push(&mut self, elem: T) {
+        self.head = ListLink::Cons(elem, Box::new(std::mem::replace(&mut self.head, ListLink::Nil)));
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+    
+        match std::mem::replace(&mut self.head, ListLink::Nil) {
+            ListLink::Nil => None,
+            ListLink::Cons(e, next) => {
+                self.head = *next;
+                Some(e)
+            }
+        }
+    }
+
+    // return a referece to the first element of the list
+    pub fn peek(&self) -> Option<&T> {
+        match &self.head{
+            ListLink::Nil => None,
+            ListLink::Cons(e, _ ) => Some(e),
+        }
+    }
+
+    // return an interator over the list values
+    pub fn iter(&self) -> ListIter<'_, T> {
+       ListIter{ curr: &self.head }
+    }
+
+    // take the first n elements of the list and return a new list with them
+    pub fn take(&mut self, n: usize) -> List<T>{
+        let mut list = List::new();
+        let mut list_rev = List::new();
+        for _ in 0..n{
+            let elem = self.pop();
+            if elem.is_none(){
+                break;
+            }
+            list.push(elem.unwrap());
+        }
+        while let Some(e) = list.pop() {
+            list_rev.push(e);
+        }
+        list_rev
+    }
+    
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+pub struct ListIter<'a, T> {
+    curr: &'a ListLink<T>,
+}
+
+impl<'a, T> Iterator for ListIter<'a,T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self.curr {
+            ListLink::Cons(e,next) => {self.curr = next;Some(e)}
+            ListLink::Nil => None
+        }
+    }
+}
+
+ 
+
+// *****
+// Persistent list: a list is never changed, prepend and tail return new lists that share
+// all their nodes with the old one, so copies of a list (and of its history) are O(1).
+// The nodes are shared through Rc in PList, and through Arc in sync::PList, which can be
+// sent to other threads. The two are the same code, written once by this macro.
+macro_rules! persistent_list {
+    ($ptr:ident) => {
+        use std::fmt;
+
+        type Link<T> = Option<$ptr<PNode<T>>>;
+
+        struct PNode<T> {
+            elem: T,
+            next: Link<T>,
+            // length of the list starting at this node
+            len: usize,
+        }
+
+        pub struct PList<T> {
+            head: Link<T>,
+        }
+
+        impl<T> PList<T> {
+            pub fn new() -> Self {
+                PList { head: None }
+            }
+
+            pub fn len(&self) -> usize {
+                self.head.as_ref().map_or(0, |node| node.len)
+            }
+
+            pub fn is_empty(&self) -> bool {
+                self.head.is_none()
+            }
+
+            // a new list with elem in front of the elements of this one
+            pub fn prepend(&self, elem: T) -> PList<T> {
+                let len = self.len() + 1;
+                PList { head: Some($ptr::new(PNode { elem, next: self.head.clone(), len })) }
+            }
+
+            // the list without its first element (empty if this one is empty)
+            pub fn tail(&self) -> PList<T> {
+                PList { head: self.head.as_ref().and_then(|node| node.next.clone()) }
+            }
+
+            pub fn head(&self) -> Option<&T> {
+                self.head.as_ref().map(|node| &node.elem)
+            }
+
+            pub fn iter(&self) -> PListIter<'_, T> {
+                PListIter { curr: self.head.as_deref() }
+            }
+
+            // true if the two lists are the same nodes, not just equal elements
+            pub fn ptr_eq(&self, other: &PList<T>) -> bool {
+                match (&self.head, &other.head) {
+                    (Some(a), Some(b)) => $ptr::ptr_eq(a, b),
+                    (None, None) => true,
+                    _ => false,
+                }
+            }
+        }
+
+        // dropping a node drops the next one, recursively: free the nodes in a loop
+        // instead, stopping at the first one still shared with another list. into_inner
+        // and not try_unwrap: two threads dropping the last two owners of a node could
+        // both see it shared, and the last drop would free the rest recursively
+        impl<T> Drop for PList<T> {
+            fn drop(&mut self) {
+                let mut curr = self.head.take();
+                while let Some(mut node) = curr.and_then($ptr::into_inner) {
+                    curr = node.next.take();
+                }
+            }
+        }
+
+        // a copy shares all the nodes: no T: Clone needed
+        impl<T> Clone for PList<T> {
+            fn clone(&self) -> Self {
+                PList { head: self.head.clone() }
+            }
+        }
+
+        impl<T> Default for PList<T> {
+            fn default() -> Self {
+                PList::new()
+            }
+        }
+
+        impl<T: PartialEq> PartialEq for PList<T> {
+            fn eq(&self, other: &Self) -> bool {
+                self.len() == other.len() && self.iter().eq(other.iter())
+            }
+        }
+
+        impl<T: fmt::Debug> fmt::Debug for PList<T> {
+            fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+                f.debug_list().entries(self.iter()).finish()
+            }
+        }
+
+        // the list has the elements in the order of the iterator
+        impl<T> FromIterator<T> for PList<T> {
+            fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+                let elems: Vec<T> = iter.into_iter().collect();
+                elems.into_iter().rev().fold(PList::new(), |list, elem| list.prepend(elem))
+            }
+        }
+
+        impl<'a, T> IntoIterator for &'a PList<T> {
+            type Item = &'a T;
+            type IntoIter = PListIter<'a, T>;
+
+            fn into_iter(self) -> PListIter<'a, T> {
+                self.iter()
+            }
+        }
+
+        pub struct PListIter<'a, T> {
+            curr: Option<&'a PNode<T>>,
+        }
+
+        impl<'a, T> Iterator for PListIter<'a, T> {
+            type Item = &'a T;
+
+            fn next(&mut self) -> Option<&'a T> {
+                self.curr.map(|node| {
+                    self.curr = node.next.as_deref();
+                    &node.elem
+                })
+            }
+
+            fn size_hint(&self) -> (usize, Option<usize>) {
+                let len = self.curr.map_or(0, |node| node.len);
+                (len, Some(len))
+            }
+        }
+
+        impl<T> ExactSizeIterator for PListIter<'_, T> {}
+    };
+}
+
+mod persistent {
+    use std::rc::Rc;
+
+    persistent_list!(Rc);
+}
+
+pub mod sync {
+    use std::sync::Arc;
+
+    persistent_list!(Arc);
+}
+
+pub use persistent::{PList, PListIter};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn prepend_and_tail_share_nodes() {
+        let empty = PList::new();
+        let a = empty.prepend(1);
+        let b = a.prepend(2);
+        let c = a.prepend(3);
+        assert_eq!(b.iter().copied().collect::<Vec<_>>(), [2, 1]);
+        assert_eq!(c.iter().copied().collect::<Vec<_>>(), [3, 1]);
+        assert!(b.tail().ptr_eq(&a) && c.tail().ptr_eq(&a));
+        assert_eq!((a.head(), a.len(), b.len()), (Some(&1), 1, 2));
+        assert!(a.tail().is_empty() && a.tail().tail().is_empty());
+        assert_eq!(empty.head(), None);
+        // the old versions are still there
+        drop(b);
+        assert_eq!(format!("{:?} {:?}", a, c), "[1] [3, 1]");
+    }
+
+    #[test]
+    fn collect_keeps_the_order() {
+        let l: PList<i32> = (1..=4).collect();
+        assert_eq!(l.iter().len(), 4);
+        assert_eq!((&l).into_iter().copied().collect::<Vec<_>>(), [1, 2, 3, 4]);
+        assert_eq!(l.tail(), (2..=4).collect());
+        assert_ne!(l, l.tail());
+    }
+
+    #[test]
+    fn shared_tails_outlive_the_lists() {
+        use std::rc::Rc;
+        let counter = Rc::new(());
+        let base: PList<Rc<()>> = (0..5).map(|_| counter.clone()).collect();
+        let long = base.prepend(counter.clone()).prepend(counter.clone());
+        let short = base.tail().tail();
+        drop(base);
+        drop(long);
+        assert_eq!(Rc::strong_count(&counter), 4);
+        drop(short);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn long_lists_drop_without_overflow() {
+        let l: PList<u32> = (0..1_000_000).collect();
+        let shared = l.tail();
+        drop(l);
+        assert_eq!(shared.len(), 999_999);
+    }
+
+    #[test]
+    fn sync_lists_go_to_other_threads() {
+        let history: sync::PList<u32> = (0..100).collect();
+        let handles: Vec<_> = (0..4)
+            .map(|i| {
+                let history = history.prepend(1000 + i);
+                std::thread::spawn(move || history.iter().sum::<u32>())
+            })
+            .collect();
+        let sums: Vec<u32> = handles.into_iter().map(|h| h.join().unwrap()).collect();
+        assert_eq!(sums, [5950, 5951, 5952, 5953]);
+    }
+
+    #[test]
+    fn long_sync_lists_drop_from_many_threads() {
+        // the threads drop the last owners of the shared nodes at the same time
+        for _ in 0..10 {
+            let l: sync::PList<u32> = (0..200_000).collect();
+            let barrier = std::sync::Barrier::new(4);
+            std::thread::scope(|s| {
+                for _ in 0..4 {
+                    let (l, barrier) = (l.clone(), &barrier);
+                    s.spawn(move || {
+                        barrier.wait();
+                        drop(l);
+                    });
+                }
+                drop(l);
+            });
+        }
+    }
+}
@@ -0,0 +1,398 @@
+use std::fmt;
+
+#[derive(Debug)]
+pub struct Node<T> {
+    elem: T,
+    next: NodeLink<T>,
+}
+
+impl<T> Node<T> {
+    pub fn new(elem: T) -> Self{
+        Self { elem, next: None }
+    }
+}
+
+type NodeLink<T> = Option<Box<Node<T>>>;
+
+pub struct List<T> {
+    head: NodeLink<T>,
+    // kept up to date by every method, so that len() doesn't walk the list
+    len: usize,
+}
+
+// for this implementattion, since we are using option, take a look at the take method in Option<T>.
+// It allows to move the value of the option into another option and replace it with None
+// let mut a = Some(5);
+// let b = a.take(); // a is now None and b is Some(5)
+impl<T> List<T> {
+    pub fn new() -> Self {
+        // create a empty List
+        Self { head: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.head.is_none()
+    }
+
+    // insert a new element at the beginning of the list
+    pub fn push(&mut self, elem: T) {
+        self.head = Some(Box::new(Node{elem, next: self.head.take()}));
+        self.len += 1;
+    }
+
+    pub fn pop(&mut self) -> Option<T> {
+        self.head.take().map(|node| {
+            self.head = node.next;
+            self.len -= 1;
+            node.elem
+        })
+    }
+
+    // return a referece to the first element of the list
+    pub fn peek(&self) -> Option<&T> {
+        self.head.as_ref().map(|node| &node.elem)
+    }
+
+    pub fn peek_mut(&mut self) -> Option<&mut T> {
+        self.head.as_mut().map(|node| &mut node.elem)
+    }
+
+    // return an interator over the list values, from the head
+    pub fn iter(&self) -> ListIter<'_, T> {
+        ListIter{ curr: self.head.as_deref(), len: self.len }
+    }
+
+    pub fn iter_mut(&mut self) -> ListIterMut<'_, T> {
+        ListIterMut{ curr: self.head.as_deref_mut(), len: self.len }
+    }
+
+    pub fn clear(&mut self) {
+        *self = List::new();
+    }
+
+    // take the first n elements of the list and return a new list with them
+    pub fn take(&mut self, n: usize) -> List<T>{
+        let rest = self.split_off(n.min(self.len));
+        std::mem::replace(self, rest)
+    }
+
+    // the elements from position at on are moved to the returned list.
+    // panics if at > len, like Vec::split_off
+    pub fn split_off(&mut self, at: usize) -> List<T> {
+        assert!(at <= self.len, "split_off: index {} out of bounds (len {})", at, self.len);
+        let link = self.link_at(at);
+        let head = link.take();
+        let tail = List { head, len: self.len - at };
+        self.len = at;
+        tail
+    }
+
+    // move all the elements of other at the end of the list, leaving other empty
+    pub fn append(&mut self, other: &mut List<T>) {
+        let len = other.len;
+        *self.link_at(self.len) = other.head.take();
+        self.len += len;
+        other.len = 0;
+    }
+
+    // reverse the list in place, without moving the elements
+    pub fn reverse(&mut self) {
+        let mut reversed: NodeLink<T> = None;
+        let mut curr = self.head.take();
+        while let Some(mut node) = curr {
+            curr = node.next.take();
+            node.next = reversed;
+            reversed = Some(node);
+        }
+        self.head = reversed;
+    }
+
+    // keep only the elements for which f returns true, in the same order
+    pub fn retain<F: FnMut(&T) -> bool>(&mut self, mut f: F) {
+        let mut removed = 0;
+        let mut link = &mut self.head;
+        while let Some(node) = link {
+            if f(&node.elem) {
+                link = &mut link.as_mut().unwrap().next;
+            } else {
+                *link = node.next.take();
+                removed += 1;
+            }
+        }
+        self.len -= removed;
+    }
+
+    pub fn contains(&self, x: &T) -> bool
+    where
+        T: PartialEq,
+    {
+        self.iter().any(|e| e == x)
+    }
+
+    // the link to the node at position i (the one after the last node if i == len)
+    fn link_at(&mut self, i: usize) -> &mut NodeLink<T> {
+        let mut link = &mut self.head;
+        for _ in 0..i {
+            link = &mut link.as_mut().expect("index checked by the caller").next;
+        }
+        link
+    }
+}
+
+// the default drop would be recursive (each Box drops the next one) and overflow
+// the stack on long lists: unlink the nodes one at a time instead
+impl<T> Drop for List<T> {
+    fn drop(&mut self) {
+        let mut curr = self.head.take();
+        while let Some(mut node) = curr {
+            curr = node.next.take();
+        }
+    }
+}
+
+impl<T> Default for List<T> {
+    fn default() -> Self {
+        List::new()
+    }
+}
+
+impl<T: Clone> Clone for List<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for List<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for List<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for List<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+// the elements are added at the end, so that the list has the same order as the iterator
+impl<T> Extend<T> for List<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        let mut added = 0;
+        let mut link = self.link_at(self.len);
+        for elem in iter {
+            let node = link.insert(Box::new(Node::new(elem)));
+            link = &mut node.next;
+            added += 1;
+        }
+        self.len += added;
+    }
+}
+
+impl<T> FromIterator<T> for List<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = List::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> IntoIterator for List<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a List<T> {
+    type Item = &'a T;
+    type IntoIter = ListIter<'a, T>;
+
+    fn into_iter(self) -> ListIter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut List<T> {
+    type Item = &'a mut T;
+    type IntoIter = ListIterMut<'a, T>;
+
+    fn into_iter(self) -> ListIterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+pub struct ListIter<'a, T> {
+    curr: Option<&'a Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for ListIter<'a,T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.curr.map(|node| {
+            self.curr = node.next.as_deref();
+            self.len -= 1;
+            &node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for ListIter<'_, T> {}
+
+pub struct ListIterMut<'a, T> {
+    curr: Option<&'a mut Node<T>>,
+    len: usize,
+}
+
+impl<'a, T> Iterator for ListIterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.curr.take().map(|node| {
+            self.curr = node.next.as_deref_mut();
+            self.len -= 1;
+            &mut node.elem
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T> ExactSizeIterator for ListIterMut<'_, T> {}
+
+pub struct IntoIter<T>(List<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn list(v: &[i32]) -> List<i32> {
+        v.iter().copied().collect()
+    }
+
+    fn to_vec(l: &List<i32>) -> Vec<i32> {
+        l.iter().copied().collect()
+    }
+
+    #[test]
+    fn push_pop_peek() {
+        let mut l = List::new();
+        assert_eq!(l.pop(), None);
+        assert_eq!(l.peek(), None);
+        l.push(1);
+        l.push(2);
+        assert_eq!(l.len(), 2);
+        assert_eq!(l.peek(), Some(&2));
+        *l.peek_mut().unwrap() = 3;
+        assert_eq!(l.pop(), Some(3));
+        assert_eq!(l.pop(), Some(1));
+        assert_eq!(l.pop(), None);
+        assert!(l.is_empty());
+    }
+
+    #[test]
+    fn iterators_keep_the_order() {
+        let mut l = list(&[1, 2, 3]);
+        assert_eq!(l.iter().len(), 3);
+        for x in &mut l {
+            *x *= 10;
+        }
+        assert_eq!(to_vec(&l), [10, 20, 30]);
+        assert_eq!(l.into_iter().collect::<Vec<_>>(), [10, 20, 30]);
+    }
+
+    #[test]
+    fn extend_appends_at_the_end() {
+        let mut l = list(&[1, 2]);
+        l.extend([3, 4]);
+        l.push(0);
+        assert_eq!(to_vec(&l), [0, 1, 2, 3, 4]);
+        assert_eq!(l.len(), 5);
+    }
+
+    #[test]
+    fn take_split_off_append() {
+        let mut l = list(&[1, 2, 3, 4, 5]);
+        let first = l.take(2);
+        assert_eq!((to_vec(&first), to_vec(&l)), (vec![1, 2], vec![3, 4, 5]));
+        assert_eq!(to_vec(&l.take(10)), [3, 4, 5]);
+        assert!(l.is_empty());
+
+        let mut l = list(&[1, 2, 3, 4, 5]);
+        let mut tail = l.split_off(3);
+        assert_eq!((to_vec(&l), to_vec(&tail)), (vec![1, 2, 3], vec![4, 5]));
+        assert_eq!(l.split_off(3).len(), 0);
+        l.append(&mut tail);
+        assert_eq!((l.len(), tail.len()), (5, 0));
+        assert_eq!(l, list(&[1, 2, 3, 4, 5]));
+        tail.append(&mut l);
+        assert_eq!((to_vec(&tail), l.len()), (vec![1, 2, 3, 4, 5], 0));
+    }
+
+    #[test]
+    #[should_panic]
+    fn split_off_out_of_bounds() {
+        list(&[1, 2]).split_off(3);
+    }
+
+    #[test]
+    fn reverse_retain_contains() {
+        let mut l = list(&[1, 2, 3, 4, 5, 6]);
+        l.reverse();
+        assert_eq!(to_vec(&l), [6, 5, 4, 3, 2, 1]);
+        l.retain(|x| x % 2 == 1);
+        assert_eq!(to_vec(&l), [5, 3, 1]);
+        assert_eq!(l.len(), 3);
+        assert!(l.contains(&3) && !l.contains(&4));
+        l.retain(|_| false);
+        assert_eq!(l.len(), 0);
+        assert!(l.is_empty());
+    }
+
+    #[test]
+    fn clone_eq_debug() {
+        let l = list(&[1, 2, 3]);
+        let c = l.clone();
+        assert_eq!(l, c);
+        assert_ne!(l, list(&[1, 2]));
+        assert_eq!(format!("{:?}", c), "[1, 2, 3]");
+    }
+
+    #[test]
+    fn long_lists_drop_without_overflow() {
+        let l: List<u32> = (0..1_000_000).collect();
+        let c = l.clone();
+        assert_eq!(c.len(), 1_000_000);
+        drop(l);
+        drop(c);
+    }
+}
@@ -0,0 +1,560 @@
+// *****
+// double linked list suggestion: use Rc, since we need more than one reference to the same node
+// for mutating the list and changing the next and prev fields we also need to be able to mutate the node, therefore we can use RefCell
+
+// how to access content of Rc<RefCell<T>>:
+// es let a = Rc::new(RefCell::new(5));
+// let mut x = (*a).borrow_mut();  // with (*a) we dereference the Rc, with (*a).borrow_mut() we get a mutable reference to the content of the RefCell
+// *x = 6; // we can now change the content of the RefCell
+
+// to take a value from a Rc (useful when popping a value from the list): usually it is not possible since it may be referenced elsewhere.
+// if you can guarantee it's the only reference to the value  youu can use Rc::try_unwrap(a).unwrap().into_inner() to get the value
+// it first takes out the value from the Rc, then it tries to unwrap the value from the Result, and finally it takes the inner value from the Result
+// see here
+// https://stackoverflow.com/questions/70404603/how-to-return-the-contents-of-an-rc
+
+// other hint that may be useful: Option<T> has a default clone implementation which calls the clone of T. Therefore: 
+// Some(T).clone() ->  Some(T.clone())
+// None.clone() -> None
+
+use std::cell::{Ref, RefCell, RefMut};
+use std::fmt;
+use std::marker::PhantomData;
+use std::rc::{Rc, Weak};
+
+type Link<T> = Rc<RefCell<DNode<T>>>;
+type NodeLink<T> = Option<Link<T>>; // we define a type alias for better readibility
+// Example
+type NodeBackLink<T> = Option<Weak<RefCell<DNode<T>>>>;
+
+struct DNode<T> {
+    elem: T,
+    prev: NodeBackLink<T>, // here we can't put NodeLink to avoid a cycle reference, what do we use?
+    next: NodeLink<T>
+}
+
+pub struct DList<T> {
+    head: NodeLink<T>,
+    tail: NodeLink<T>,
+    // the number of nodes, also tells when head and tail are the same node
+    len: usize,
+}
+
+impl<T> DList<T> {
+    pub fn new() -> Self {
+        // create a empty List
+        Self { head: None, tail: None, len: 0 }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        let next = self.head.clone();
+        self.link_between(None, next, elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        let prev = self.tail.clone();
+        self.link_between(prev, None, elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        let head = self.head.clone()?;
+        Some(self.unlink(head))
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        let tail = self.tail.clone()?;
+        Some(self.unlink(tail))
+    }
+
+    // the element lives in a RefCell: we can only lend it through a Ref guard
+    pub fn peek_front(&self) -> Option<Ref<'_, T>> {
+        self.head.as_ref().map(|node| Ref::map(node.borrow(), |n| &n.elem))
+    }
+
+    pub fn peek_back(&self) -> Option<Ref<'_, T>> {
+        self.tail.as_ref().map(|node| Ref::map(node.borrow(), |n| &n.elem))
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.head.as_ref().map(|node| RefMut::map(node.borrow_mut(), |n| &mut n.elem))
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<RefMut<'_, T>> {
+        self.tail.as_ref().map(|node| RefMut::map(node.borrow_mut(), |n| &mut n.elem))
+    }
+
+    // insert elem at position i, so that it's the i-th element from the front.
+    // panics if i > len, like VecDeque::insert
+    pub fn insert_at(&mut self, i: usize, elem: T) {
+        assert!(i <= self.len, "insert_at: index {} out of bounds (len {})", i, self.len);
+        if i == self.len {
+            self.push_back(elem);
+        } else {
+            let node = self.node_at(i);
+            let prev = node.borrow().prev.as_ref().and_then(Weak::upgrade);
+            self.link_between(prev, Some(node), elem);
+        }
+    }
+
+    // remove the i-th element from the front, None if i >= len
+    pub fn remove_at(&mut self, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
+        }
+        let node = self.node_at(i);
+        Some(self.unlink(node))
+    }
+
+    // the n-th element from the front, removed: the old name of remove_at
+    #[deprecated(note = "use remove_at")]
+    pub fn popn(&mut self, n: usize) -> Option<T> {
+        self.remove_at(n)
+    }
+
+    // the elements are cloned out of their RefCell: a plain &T can't outlive the
+    // borrow of the node
+    pub fn iter(&self) -> Iter<'_, T>
+    where
+        T: Clone,
+    {
+        Iter { front: self.head.clone(), back: self.tail.clone(), len: self.len, list: PhantomData }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor { current: self.head.clone(), index: 0, list: self }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor { current: self.tail.clone(), index: self.len.saturating_sub(1), list: self }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.head.clone(), index: 0, list: self }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let index = self.len.saturating_sub(1);
+        CursorMut { current: self.tail.clone(), index, list: self }
+    }
+
+    // the node at position i < len, reached from the closer end
+    fn node_at(&self, i: usize) -> Link<T> {
+        debug_assert!(i < self.len);
+        if i <= self.len / 2 {
+            let mut node = self.head.clone().expect("non empty list");
+            for _ in 0..i {
+                let next = node.borrow().next.clone().expect("i < len");
+                node = next;
+            }
+            node
+        } else {
+            let mut node = self.tail.clone().expect("non empty list");
+            for _ in i + 1..self.len {
+                let prev = node.borrow().prev.as_ref().and_then(Weak::upgrade).expect("i < len");
+                node = prev;
+            }
+            node
+        }
+    }
+
+    // create a node for elem between prev and next (None at the two ends of the list)
+    fn link_between(&mut self, prev: NodeLink<T>, next: NodeLink<T>, elem: T) -> Link<T> {
+        let node = Rc::new(RefCell::new(DNode { elem, prev: prev.as_ref().map(Rc::downgrade), next: next.clone() }));
+        match &prev {
+            Some(p) => p.borrow_mut().next = Some(node.clone()),
+            None => self.head = Some(node.clone()),
+        }
+        match &next {
+            Some(n) => n.borrow_mut().prev = Some(Rc::downgrade(&node)),
+            None => self.tail = Some(node.clone()),
+        }
+        self.len += 1;
+        node
+    }
+
+    // detach node from the list and take its element out.
+    // The caller must not keep other clones of the Rc around
+    fn unlink(&mut self, node: Link<T>) -> T {
+        let (prev, next) = {
+            let mut n = node.borrow_mut();
+            (n.prev.take().and_then(|w| w.upgrade()), n.next.take())
+        };
+        match &prev {
+            Some(p) => p.borrow_mut().next = next.clone(),
+            None => self.head = next.clone(),
+        }
+        match &next {
+            Some(n) => n.borrow_mut().prev = prev.as_ref().map(Rc::downgrade),
+            None => self.tail = prev,
+        }
+        self.len -= 1;
+        match Rc::try_unwrap(node) {
+            Ok(cell) => cell.into_inner().elem,
+            Err(_) => unreachable!("an unlinked node has no other owners"),
+        }
+    }
+}
+
+// dropping the head would drop the whole chain recursively: pop one node at a time
+impl<T> Drop for DList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for DList<T> {
+    fn default() -> Self {
+        DList::new()
+    }
+}
+
+impl<T: Clone> Clone for DList<T> {
+    fn clone(&self) -> Self {
+        self.iter().collect()
+    }
+}
+
+impl<T: Clone + PartialEq> PartialEq for DList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: fmt::Debug> fmt::Debug for DList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut list = f.debug_list();
+        let mut curr = self.head.clone();
+        while let Some(node) = curr {
+            list.entry(&node.borrow().elem);
+            curr = node.borrow().next.clone();
+        }
+        list.finish()
+    }
+}
+
+impl<T> Extend<T> for DList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for DList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = DList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> IntoIterator for DList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+pub struct IntoIter<T>(DList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// front and back meet in the middle: len tells when they have crossed
+pub struct Iter<'a, T> {
+    front: NodeLink<T>,
+    back: NodeLink<T>,
+    len: usize,
+    list: PhantomData<&'a DList<T>>,
+}
+
+impl<T: Clone> Iterator for Iter<'_, T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.front.take()?;
+        let node = node.borrow();
+        self.front = node.next.clone();
+        self.len -= 1;
+        Some(node.elem.clone())
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<T: Clone> DoubleEndedIterator for Iter<'_, T> {
+    fn next_back(&mut self) -> Option<T> {
+        if self.len == 0 {
+            return None;
+        }
+        let node = self.back.take()?;
+        let node = node.borrow();
+        self.back = node.prev.as_ref().and_then(Weak::upgrade);
+        self.len -= 1;
+        Some(node.elem.clone())
+    }
+}
+
+impl<T: Clone> ExactSizeIterator for Iter<'_, T> {}
+
+// Cursors point to an element of the list, or to the "ghost" position between the
+// back and the front (current is None), like std::collections::linked_list::Cursor:
+// moving past either end goes to the ghost, moving again wraps to the other end.
+pub struct Cursor<'a, T> {
+    current: NodeLink<T>,
+    index: usize,
+    list: &'a DList<T>,
+}
+
+impl<T> Cursor<'_, T> {
+    // the position of the cursor, None at the ghost
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn current(&self) -> Option<Ref<'_, T>> {
+        self.current.as_ref().map(|node| Ref::map(node.borrow(), |n| &n.elem))
+    }
+
+    pub fn move_next(&mut self) {
+        (self.current, self.index) = step_next(&self.current, self.index, self.list);
+    }
+
+    pub fn move_prev(&mut self) {
+        (self.current, self.index) = step_prev(&self.current, self.index, self.list);
+    }
+}
+
+// A cursor that can also change the list: insertions and removals at the cursor
+// are O(1).
+pub struct CursorMut<'a, T> {
+    current: NodeLink<T>,
+    index: usize,
+    list: &'a mut DList<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.current.as_ref().map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<RefMut<'_, T>> {
+        self.current.as_ref().map(|node| RefMut::map(node.borrow_mut(), |n| &mut n.elem))
+    }
+
+    pub fn move_next(&mut self) {
+        (self.current, self.index) = step_next(&self.current, self.index, self.list);
+    }
+
+    pub fn move_prev(&mut self) {
+        (self.current, self.index) = step_prev(&self.current, self.index, self.list);
+    }
+
+    // insert after the current element; at the ghost, at the front of the list
+    pub fn insert_after(&mut self, elem: T) {
+        let next = match &self.current {
+            Some(node) => node.borrow().next.clone(),
+            None => self.list.head.clone(),
+        };
+        self.list.link_between(self.current.clone(), next, elem);
+        if self.current.is_none() {
+            // the ghost stays after the back
+            self.index = self.list.len;
+        }
+    }
+
+    // insert before the current element; at the ghost, at the back of the list
+    pub fn insert_before(&mut self, elem: T) {
+        let prev = match &self.current {
+            Some(node) => node.borrow().prev.as_ref().and_then(Weak::upgrade),
+            None => self.list.tail.clone(),
+        };
+        self.list.link_between(prev, self.current.clone(), elem);
+        self.index += 1;
+    }
+
+    // remove the current element and move to the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current.take()?;
+        self.current = node.borrow().next.clone();
+        if self.current.is_none() {
+            self.index = self.list.len - 1;
+        }
+        Some(self.list.unlink(node))
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor { current: self.current.clone(), index: self.index, list: self.list }
+    }
+}
+
+// At the ghost the index is len, so that index + 1 is the position of the next element.
+fn step_next<T>(current: &NodeLink<T>, index: usize, list: &DList<T>) -> (NodeLink<T>, usize) {
+    match current {
+        Some(node) => match node.borrow().next.clone() {
+            Some(next) => (Some(next), index + 1),
+            None => (None, list.len),
+        },
+        None => (list.head.clone(), 0),
+    }
+}
+
+fn step_prev<T>(current: &NodeLink<T>, index: usize, list: &DList<T>) -> (NodeLink<T>, usize) {
+    match current {
+        Some(node) => match node.borrow().prev.as_ref().and_then(Weak::upgrade) {
+            Some(prev) => (Some(prev), index - 1),
+            None => (None, list.len),
+        },
+        None => (list.tail.clone(), list.len.saturating_sub(1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_vec(l: &DList<i32>) -> Vec<i32> {
+        l.iter().collect()
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut l = DList::new();
+        assert_eq!((l.pop_front(), l.pop_back()), (None, None));
+        l.push_back(1);
+        assert_eq!(l.pop_back(), Some(1));
+        assert!(l.is_empty());
+        l.push_front(2);
+        l.push_front(3);
+        l.push_back(1);
+        assert_eq!(to_vec(&l), [3, 2, 1]);
+        assert_eq!((*l.peek_front().unwrap(), *l.peek_back().unwrap()), (3, 1));
+        *l.peek_back_mut().unwrap() = 0;
+        assert_eq!(l.pop_back(), Some(0));
+        assert_eq!(l.pop_front(), Some(3));
+        assert_eq!(l.pop_front(), Some(2));
+        assert_eq!((l.len(), l.peek_front().is_none(), l.peek_back().is_none()), (0, true, true));
+    }
+
+    #[test]
+    fn insert_and_remove_at() {
+        let mut l: DList<i32> = (0..5).collect();
+        l.insert_at(0, -1);
+        l.insert_at(6, 5);
+        l.insert_at(4, 10);
+        assert_eq!(to_vec(&l), [-1, 0, 1, 2, 10, 3, 4, 5]);
+        assert_eq!(l.remove_at(4), Some(10));
+        assert_eq!(l.remove_at(0), Some(-1));
+        assert_eq!(l.remove_at(5), Some(5));
+        assert_eq!(l.remove_at(5), None);
+        assert_eq!(l.remove_at(1), Some(1));
+        assert_eq!(to_vec(&l), [0, 2, 3, 4]);
+    }
+
+    #[test]
+    #[allow(deprecated)]
+    fn popn_is_remove_at() {
+        let mut l: DList<i32> = (0..4).collect();
+        assert_eq!(l.popn(2), Some(2));
+        assert_eq!(l.popn(3), None);
+        assert_eq!(to_vec(&l), [0, 1, 3]);
+    }
+
+    #[test]
+    fn double_ended_iterators() {
+        let l: DList<i32> = (1..=4).collect();
+        assert_eq!(l.iter().rev().collect::<Vec<_>>(), [4, 3, 2, 1]);
+        let mut it = l.iter();
+        assert_eq!((it.next(), it.next_back(), it.next(), it.next_back(), it.next()), (Some(1), Some(4), Some(2), Some(3), None));
+        let mut it = l.clone().into_iter();
+        assert_eq!((it.next_back(), it.len()), (Some(4), 3));
+        assert_eq!(it.collect::<Vec<_>>(), [1, 2, 3]);
+        assert_eq!(format!("{:?}", l), "[1, 2, 3, 4]");
+    }
+
+    #[test]
+    fn cursor_walks_and_wraps() {
+        let l: DList<i32> = (0..3).collect();
+        let mut c = l.cursor_front();
+        assert_eq!((c.index(), *c.current().unwrap()), (Some(0), 0));
+        c.move_next();
+        c.move_next();
+        assert_eq!((c.index(), *c.current().unwrap()), (Some(2), 2));
+        c.move_next();
+        assert_eq!((c.index(), c.current().is_none()), (None, true));
+        c.move_next();
+        assert_eq!(c.index(), Some(0));
+        c.move_prev();
+        c.move_prev();
+        assert_eq!((c.index(), *c.current().unwrap()), (Some(2), 2));
+        assert!(DList::<i32>::new().cursor_back().current().is_none());
+    }
+
+    #[test]
+    fn cursor_mut_edits() {
+        let mut l: DList<i32> = (0..4).collect();
+        let mut c = l.cursor_front_mut();
+        c.move_next();
+        *c.current().unwrap() = 10;
+        c.insert_before(5);
+        c.insert_after(15);
+        assert_eq!(c.index(), Some(2));
+        assert_eq!(c.remove_current(), Some(10));
+        assert_eq!((c.index(), *c.current().unwrap()), (Some(2), 15));
+        c.move_prev();
+        c.move_prev();
+        c.move_prev();
+        // at the ghost: insert_after goes to the front, insert_before to the back
+        c.insert_after(-1);
+        c.insert_before(9);
+        assert_eq!(c.index(), None);
+        assert_eq!(c.as_cursor().index(), None);
+        c.move_prev();
+        assert_eq!(c.remove_current(), Some(9));
+        assert_eq!(c.index(), None);
+        assert_eq!(to_vec(&l), [-1, 0, 5, 15, 2, 3]);
+        assert_eq!(l.len(), 6);
+    }
+
+    #[test]
+    fn long_lists_drop_without_overflow() {
+        let l: DList<u32> = (0..1_000_000).collect();
+        assert_eq!(l.len(), 1_000_000);
+    }
+}
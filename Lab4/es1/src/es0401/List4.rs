@@ -0,0 +1,758 @@
+// *****
+// The same doubly linked list of List3, with the nodes linked by raw pointers instead of
+// Rc<RefCell<..>>: no reference counts and no borrow flags to update while walking the
+// list, and peek/iter can lend plain &T and &mut T.
+//
+// The list owns all its nodes: each one is allocated with Box::leak when it's linked
+// and given back to a Box (and freed) when it's unlinked. Every unsafe block relies on
+// these invariants, kept by link_between, unlink and move_to_front, the only places
+// that change links:
+// - head/tail are None iff len == 0, otherwise they point to the first/last node;
+// - every node reachable from head is a live allocation owned by this list, and
+//   node.next.prev == node, node.prev.next == node.
+//
+// cargo +nightly miri test checks the unit tests and the property tests for undefined
+// behaviour.
+
+use std::fmt;
+use std::marker::PhantomData;
+use std::ptr::NonNull;
+
+type NodeLink<T> = Option<NonNull<Node<T>>>;
+
+struct Node<T> {
+    elem: T,
+    prev: NodeLink<T>,
+    next: NodeLink<T>,
+}
+
+pub struct DList<T> {
+    head: NodeLink<T>,
+    tail: NodeLink<T>,
+    len: usize,
+    // we own the nodes: drop check and variance as for a Box<Node<T>>
+    marker: PhantomData<Box<Node<T>>>,
+}
+
+// the list is used like a Box<Node<T>>: it can go to another thread if T can
+unsafe impl<T: Send> Send for DList<T> {}
+unsafe impl<T: Sync> Sync for DList<T> {}
+
+impl<T> DList<T> {
+    pub fn new() -> Self {
+        Self { head: None, tail: None, len: 0, marker: PhantomData }
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn clear(&mut self) {
+        while self.pop_front().is_some() {}
+    }
+
+    pub fn push_front(&mut self, elem: T) {
+        self.link_between(None, self.head, elem);
+    }
+
+    pub fn push_back(&mut self, elem: T) {
+        self.link_between(self.tail, None, elem);
+    }
+
+    pub fn pop_front(&mut self) -> Option<T> {
+        // SAFETY: head is a node of this list
+        self.head.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn pop_back(&mut self) -> Option<T> {
+        // SAFETY: tail is a node of this list
+        self.tail.map(|node| unsafe { self.unlink(node) })
+    }
+
+    pub fn peek_front(&self) -> Option<&T> {
+        // SAFETY: head is a live node, borrowed as long as the list
+        self.head.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back(&self) -> Option<&T> {
+        // SAFETY: tail is a live node, borrowed as long as the list
+        self.tail.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn peek_front_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: head is a live node, and the list is borrowed mutably
+        self.head.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn peek_back_mut(&mut self) -> Option<&mut T> {
+        // SAFETY: tail is a live node, and the list is borrowed mutably
+        self.tail.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    // insert elem at position i, so that it's the i-th element from the front.
+    // panics if i > len, like VecDeque::insert
+    pub fn insert_at(&mut self, i: usize, elem: T) {
+        assert!(i <= self.len, "insert_at: index {} out of bounds (len {})", i, self.len);
+        if i == self.len {
+            self.push_back(elem);
+        } else {
+            let node = self.node_at(i);
+            // SAFETY: node_at returns a live node of this list
+            let prev = unsafe { (*node.as_ptr()).prev };
+            self.link_between(prev, Some(node), elem);
+        }
+    }
+
+    // remove the i-th element from the front, None if i >= len
+    pub fn remove_at(&mut self, i: usize) -> Option<T> {
+        if i >= self.len {
+            return None;
+        }
+        let node = self.node_at(i);
+        // SAFETY: node_at returns a node of this list
+        Some(unsafe { self.unlink(node) })
+    }
+
+    pub fn iter(&self) -> Iter<'_, T> {
+        Iter { front: self.head, back: self.tail, len: self.len, marker: PhantomData }
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_, T> {
+        IterMut { front: self.head, back: self.tail, len: self.len, marker: PhantomData }
+    }
+
+    pub fn cursor_front(&self) -> Cursor<'_, T> {
+        Cursor { current: self.head, index: 0, list: self }
+    }
+
+    pub fn cursor_back(&self) -> Cursor<'_, T> {
+        Cursor { current: self.tail, index: self.len.saturating_sub(1), list: self }
+    }
+
+    pub fn cursor_front_mut(&mut self) -> CursorMut<'_, T> {
+        CursorMut { current: self.head, index: 0, list: self }
+    }
+
+    pub fn cursor_back_mut(&mut self) -> CursorMut<'_, T> {
+        let index = self.len.saturating_sub(1);
+        CursorMut { current: self.tail, index, list: self }
+    }
+
+    // the node at position i < len, reached from the closer end
+    fn node_at(&self, i: usize) -> NonNull<Node<T>> {
+        debug_assert!(i < self.len);
+        // SAFETY: i < len, so the walk stays on live nodes of the list
+        unsafe {
+            if i <= self.len / 2 {
+                let mut node = self.head.expect("non empty list");
+                for _ in 0..i {
+                    node = (*node.as_ptr()).next.expect("i < len");
+                }
+                node
+            } else {
+                let mut node = self.tail.expect("non empty list");
+                for _ in i + 1..self.len {
+                    node = (*node.as_ptr()).prev.expect("i < len");
+                }
+                node
+            }
+        }
+    }
+
+    // create a node for elem between prev and next, two adjacent nodes of the list
+    // (None at the two ends)
+    fn link_between(&mut self, prev: NodeLink<T>, next: NodeLink<T>, elem: T) -> NonNull<Node<T>> {
+        let node = NonNull::from(Box::leak(Box::new(Node { elem, prev, next })));
+        // SAFETY: prev and next are live nodes of the list, and no reference to them is
+        // alive while the list is borrowed mutably
+        unsafe {
+            match prev {
+                Some(p) => (*p.as_ptr()).next = Some(node),
+                None => self.head = Some(node),
+            }
+            match next {
+                Some(n) => (*n.as_ptr()).prev = Some(node),
+                None => self.tail = Some(node),
+            }
+        }
+        self.len += 1;
+        node
+    }
+
+    // detach node from the list, free it and return its element.
+    // SAFETY: node must be a node of this list; it's dangling afterwards
+    unsafe fn unlink(&mut self, node: NonNull<Node<T>>) -> T {
+        // SAFETY: the node was allocated by link_between, and the list owns it
+        let node = unsafe { Box::from_raw(node.as_ptr()) };
+        // SAFETY: the neighbours are live nodes of the list
+        unsafe {
+            match node.prev {
+                Some(p) => (*p.as_ptr()).next = node.next,
+                None => self.head = node.next,
+            }
+            match node.next {
+                Some(n) => (*n.as_ptr()).prev = node.prev,
+                None => self.tail = node.prev,
+            }
+        }
+        self.len -= 1;
+        node.elem
+    }
+}
+
+// Handles are stable pointers to the nodes, for the structures built on the list (the
+// LRU cache) that keep an index of its nodes to reach them in O(1). A handle is only
+// valid on the list that returned it, until its node is removed: the methods taking
+// one are unsafe and stay inside the crate.
+pub(crate) struct Handle<T>(NonNull<Node<T>>);
+
+impl<T> Clone for Handle<T> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<T> Copy for Handle<T> {}
+
+// the same node, whatever the elements
+impl<T> PartialEq for Handle<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.0 == other.0
+    }
+}
+
+impl<T> Eq for Handle<T> {}
+
+impl<T> DList<T> {
+    pub(crate) fn push_front_handle(&mut self, elem: T) -> Handle<T> {
+        Handle(self.link_between(None, self.head, elem))
+    }
+
+    pub(crate) fn back_handle(&self) -> Option<Handle<T>> {
+        self.tail.map(Handle)
+    }
+
+    // SAFETY for all the methods below: h must be a valid handle of this list
+
+    pub(crate) unsafe fn get(&self, h: Handle<T>) -> &T {
+        // SAFETY: the node is live, borrowed as long as the list
+        unsafe { &(*h.0.as_ptr()).elem }
+    }
+
+    pub(crate) unsafe fn get_mut(&mut self, h: Handle<T>) -> &mut T {
+        // SAFETY: the node is live, and the list is borrowed mutably
+        unsafe { &mut (*h.0.as_ptr()).elem }
+    }
+
+    pub(crate) unsafe fn remove(&mut self, h: Handle<T>) -> T {
+        // SAFETY: h is a node of this list
+        unsafe { self.unlink(h.0) }
+    }
+
+    // move the node to the front without reallocating it: the handle stays valid
+    pub(crate) unsafe fn move_to_front(&mut self, h: Handle<T>) {
+        let node = h.0;
+        if self.head == Some(node) {
+            return;
+        }
+        // SAFETY: node and its neighbours are live nodes of the list; node is not the
+        // head, so it has a prev and the list has a head
+        unsafe {
+            let (prev, next) = ((*node.as_ptr()).prev, (*node.as_ptr()).next);
+            let prev = prev.expect("not the head");
+            (*prev.as_ptr()).next = next;
+            match next {
+                Some(n) => (*n.as_ptr()).prev = Some(prev),
+                None => self.tail = Some(prev),
+            }
+            let head = self.head.expect("non empty list");
+            (*node.as_ptr()).prev = None;
+            (*node.as_ptr()).next = Some(head);
+            (*head.as_ptr()).prev = Some(node);
+            self.head = Some(node);
+        }
+    }
+}
+
+// no recursion here either: nodes are freed one at a time from the front
+impl<T> Drop for DList<T> {
+    fn drop(&mut self) {
+        self.clear();
+    }
+}
+
+impl<T> Default for DList<T> {
+    fn default() -> Self {
+        DList::new()
+    }
+}
+
+impl<T: Clone> Clone for DList<T> {
+    fn clone(&self) -> Self {
+        self.iter().cloned().collect()
+    }
+}
+
+impl<T: PartialEq> PartialEq for DList<T> {
+    fn eq(&self, other: &Self) -> bool {
+        self.len == other.len && self.iter().eq(other.iter())
+    }
+}
+
+impl<T: Eq> Eq for DList<T> {}
+
+impl<T: fmt::Debug> fmt::Debug for DList<T> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_list().entries(self.iter()).finish()
+    }
+}
+
+impl<T> Extend<T> for DList<T> {
+    fn extend<I: IntoIterator<Item = T>>(&mut self, iter: I) {
+        for elem in iter {
+            self.push_back(elem);
+        }
+    }
+}
+
+impl<T> FromIterator<T> for DList<T> {
+    fn from_iter<I: IntoIterator<Item = T>>(iter: I) -> Self {
+        let mut list = DList::new();
+        list.extend(iter);
+        list
+    }
+}
+
+impl<T> IntoIterator for DList<T> {
+    type Item = T;
+    type IntoIter = IntoIter<T>;
+
+    fn into_iter(self) -> IntoIter<T> {
+        IntoIter(self)
+    }
+}
+
+impl<'a, T> IntoIterator for &'a DList<T> {
+    type Item = &'a T;
+    type IntoIter = Iter<'a, T>;
+
+    fn into_iter(self) -> Iter<'a, T> {
+        self.iter()
+    }
+}
+
+impl<'a, T> IntoIterator for &'a mut DList<T> {
+    type Item = &'a mut T;
+    type IntoIter = IterMut<'a, T>;
+
+    fn into_iter(self) -> IterMut<'a, T> {
+        self.iter_mut()
+    }
+}
+
+pub struct IntoIter<T>(DList<T>);
+
+impl<T> Iterator for IntoIter<T> {
+    type Item = T;
+
+    fn next(&mut self) -> Option<T> {
+        self.0.pop_front()
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.0.len, Some(self.0.len))
+    }
+}
+
+impl<T> DoubleEndedIterator for IntoIter<T> {
+    fn next_back(&mut self) -> Option<T> {
+        self.0.pop_back()
+    }
+}
+
+impl<T> ExactSizeIterator for IntoIter<T> {}
+
+// front and back meet in the middle: len tells when they have crossed
+pub struct Iter<'a, T> {
+    front: NodeLink<T>,
+    back: NodeLink<T>,
+    len: usize,
+    marker: PhantomData<&'a T>,
+}
+
+impl<'a, T> Iterator for Iter<'a, T> {
+    type Item = &'a T;
+
+    fn next(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.len -= 1;
+            // SAFETY: the node is live as long as the list is borrowed, for 'a
+            unsafe {
+                self.front = (*node.as_ptr()).next;
+                &(*node.as_ptr()).elem
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for Iter<'a, T> {
+    fn next_back(&mut self) -> Option<&'a T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.len -= 1;
+            // SAFETY: as in next
+            unsafe {
+                self.back = (*node.as_ptr()).prev;
+                &(*node.as_ptr()).elem
+            }
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for Iter<'_, T> {}
+
+impl<T> Clone for Iter<'_, T> {
+    fn clone(&self) -> Self {
+        Iter { ..*self }
+    }
+}
+
+pub struct IterMut<'a, T> {
+    front: NodeLink<T>,
+    back: NodeLink<T>,
+    len: usize,
+    marker: PhantomData<&'a mut T>,
+}
+
+impl<'a, T> Iterator for IterMut<'a, T> {
+    type Item = &'a mut T;
+
+    fn next(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.front.map(|node| {
+            self.len -= 1;
+            // SAFETY: the list is borrowed mutably for 'a, and each element is lent once:
+            // len stops front and back from handing out the same node twice. Only the
+            // elem field is borrowed, the links are read through the raw pointer.
+            unsafe {
+                self.front = (*node.as_ptr()).next;
+                &mut (*node.as_ptr()).elem
+            }
+        })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (self.len, Some(self.len))
+    }
+}
+
+impl<'a, T> DoubleEndedIterator for IterMut<'a, T> {
+    fn next_back(&mut self) -> Option<&'a mut T> {
+        if self.len == 0 {
+            return None;
+        }
+        self.back.map(|node| {
+            self.len -= 1;
+            // SAFETY: as in next
+            unsafe {
+                self.back = (*node.as_ptr()).prev;
+                &mut (*node.as_ptr()).elem
+            }
+        })
+    }
+}
+
+impl<T> ExactSizeIterator for IterMut<'_, T> {}
+
+// Cursors point to an element of the list, or to the "ghost" position between the
+// back and the front (current is None), as in List3.
+pub struct Cursor<'a, T> {
+    current: NodeLink<T>,
+    index: usize,
+    list: &'a DList<T>,
+}
+
+impl<'a, T> Cursor<'a, T> {
+    // the position of the cursor, None at the ghost
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn current(&self) -> Option<&'a T> {
+        // SAFETY: the node lives as long as the list, borrowed for 'a
+        self.current.map(|node| unsafe { &(*node.as_ptr()).elem })
+    }
+
+    pub fn move_next(&mut self) {
+        (self.current, self.index) = step_next(self.current, self.index, self.list);
+    }
+
+    pub fn move_prev(&mut self) {
+        (self.current, self.index) = step_prev(self.current, self.index, self.list);
+    }
+
+    pub fn peek_next(&self) -> Option<&'a T> {
+        let next = neighbours(self.current, self.list).1?;
+        // SAFETY: as in current
+        Some(unsafe { &(*next.as_ptr()).elem })
+    }
+
+    pub fn peek_prev(&self) -> Option<&'a T> {
+        let prev = neighbours(self.current, self.list).0?;
+        // SAFETY: as in current
+        Some(unsafe { &(*prev.as_ptr()).elem })
+    }
+}
+
+impl<T> Clone for Cursor<'_, T> {
+    fn clone(&self) -> Self {
+        Cursor { ..*self }
+    }
+}
+
+// A cursor that can also change the list: insertions and removals at the cursor
+// are O(1).
+pub struct CursorMut<'a, T> {
+    current: NodeLink<T>,
+    index: usize,
+    list: &'a mut DList<T>,
+}
+
+impl<T> CursorMut<'_, T> {
+    pub fn index(&self) -> Option<usize> {
+        self.current.map(|_| self.index)
+    }
+
+    pub fn current(&mut self) -> Option<&mut T> {
+        // SAFETY: the node lives as long as the list, and the cursor borrows it mutably
+        self.current.map(|node| unsafe { &mut (*node.as_ptr()).elem })
+    }
+
+    pub fn move_next(&mut self) {
+        (self.current, self.index) = step_next(self.current, self.index, self.list);
+    }
+
+    pub fn move_prev(&mut self) {
+        (self.current, self.index) = step_prev(self.current, self.index, self.list);
+    }
+
+    pub fn peek_next(&mut self) -> Option<&mut T> {
+        let next = neighbours(self.current, self.list).1?;
+        // SAFETY: as in current
+        Some(unsafe { &mut (*next.as_ptr()).elem })
+    }
+
+    pub fn peek_prev(&mut self) -> Option<&mut T> {
+        let prev = neighbours(self.current, self.list).0?;
+        // SAFETY: as in current
+        Some(unsafe { &mut (*prev.as_ptr()).elem })
+    }
+
+    // insert after the current element; at the ghost, at the front of the list
+    pub fn insert_after(&mut self, elem: T) {
+        let next = neighbours(self.current, self.list).1;
+        self.list.link_between(self.current, next, elem);
+        if self.current.is_none() {
+            // the ghost stays after the back
+            self.index = self.list.len;
+        }
+    }
+
+    // insert before the current element; at the ghost, at the back of the list
+    pub fn insert_before(&mut self, elem: T) {
+        let prev = neighbours(self.current, self.list).0;
+        self.list.link_between(prev, self.current, elem);
+        self.index += 1;
+    }
+
+    // remove the current element and move to the next one
+    pub fn remove_current(&mut self) -> Option<T> {
+        let node = self.current?;
+        self.current = neighbours(self.current, self.list).1;
+        if self.current.is_none() {
+            self.index = self.list.len - 1;
+        }
+        // SAFETY: current is a node of the list, and the cursor doesn't point to it anymore
+        Some(unsafe { self.list.unlink(node) })
+    }
+
+    pub fn as_cursor(&self) -> Cursor<'_, T> {
+        Cursor { current: self.current, index: self.index, list: self.list }
+    }
+}
+
+// the nodes before and after current, the ghost is between tail and head
+fn neighbours<T>(current: NodeLink<T>, list: &DList<T>) -> (NodeLink<T>, NodeLink<T>) {
+    match current {
+        // SAFETY: the cursors only point to live nodes of the list
+        Some(node) => unsafe { ((*node.as_ptr()).prev, (*node.as_ptr()).next) },
+        None => (list.tail, list.head),
+    }
+}
+
+// At the ghost the index is len, so that index + 1 is the position of the next element.
+fn step_next<T>(current: NodeLink<T>, index: usize, list: &DList<T>) -> (NodeLink<T>, usize) {
+    match (current, neighbours(current, list).1) {
+        (Some(_), Some(next)) => (Some(next), index + 1),
+        (Some(_), None) => (None, list.len),
+        (None, head) => (head, 0),
+    }
+}
+
+fn step_prev<T>(current: NodeLink<T>, index: usize, list: &DList<T>) -> (NodeLink<T>, usize) {
+    match (current, neighbours(current, list).0) {
+        (Some(_), Some(prev)) => (Some(prev), index - 1),
+        (Some(_), None) => (None, list.len),
+        (None, tail) => (tail, list.len.saturating_sub(1)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn to_vec(l: &DList<i32>) -> Vec<i32> {
+        l.iter().copied().collect()
+    }
+
+    #[test]
+    fn push_pop_both_ends() {
+        let mut l = DList::new();
+        assert_eq!((l.pop_front(), l.pop_back()), (None, None));
+        l.push_back(1);
+        assert_eq!(l.pop_back(), Some(1));
+        assert!(l.is_empty());
+        l.push_front(2);
+        l.push_front(3);
+        l.push_back(1);
+        assert_eq!(to_vec(&l), [3, 2, 1]);
+        assert_eq!((l.peek_front(), l.peek_back()), (Some(&3), Some(&1)));
+        *l.peek_back_mut().unwrap() = 0;
+        *l.peek_front_mut().unwrap() += 1;
+        assert_eq!(l.pop_back(), Some(0));
+        assert_eq!(l.pop_front(), Some(4));
+        assert_eq!(l.pop_front(), Some(2));
+        assert_eq!((l.len(), l.peek_front(), l.peek_back()), (0, None, None));
+    }
+
+    #[test]
+    fn insert_and_remove_at() {
+        let mut l: DList<i32> = (0..5).collect();
+        l.insert_at(0, -1);
+        l.insert_at(6, 5);
+        l.insert_at(4, 10);
+        assert_eq!(to_vec(&l), [-1, 0, 1, 2, 10, 3, 4, 5]);
+        assert_eq!(l.remove_at(4), Some(10));
+        assert_eq!(l.remove_at(0), Some(-1));
+        assert_eq!(l.remove_at(5), Some(5));
+        assert_eq!(l.remove_at(5), None);
+        assert_eq!(to_vec(&l), [0, 1, 2, 3, 4]);
+    }
+
+    #[test]
+    fn iterators() {
+        let mut l: DList<i32> = (1..=4).collect();
+        assert_eq!(l.iter().rev().copied().collect::<Vec<_>>(), [4, 3, 2, 1]);
+        let mut it = l.iter_mut();
+        let (a, b) = (it.next().unwrap(), it.next_back().unwrap());
+        // two elements lent at the same time
+        std::mem::swap(a, b);
+        for x in it {
+            *x *= 10;
+        }
+        assert_eq!(to_vec(&l), [4, 20, 30, 1]);
+        let mut it = l.clone().into_iter();
+        assert_eq!((it.next_back(), it.len()), (Some(1), 3));
+        assert_eq!(it.collect::<Vec<_>>(), [4, 20, 30]);
+        assert_eq!(format!("{:?}", l), "[4, 20, 30, 1]");
+        assert_eq!(l, l.clone());
+    }
+
+    #[test]
+    fn cursors() {
+        let l: DList<i32> = (0..3).collect();
+        let mut c = l.cursor_front();
+        assert_eq!((c.index(), c.current(), c.peek_prev(), c.peek_next()), (Some(0), Some(&0), None, Some(&1)));
+        c.move_prev();
+        assert_eq!((c.index(), c.current(), c.peek_prev(), c.peek_next()), (None, None, Some(&2), Some(&0)));
+        c.move_prev();
+        assert_eq!((c.index(), c.current()), (Some(2), Some(&2)));
+
+        let mut l: DList<i32> = (0..4).collect();
+        let mut c = l.cursor_front_mut();
+        c.move_next();
+        *c.current().unwrap() = 10;
+        c.insert_before(5);
+        c.insert_after(15);
+        assert_eq!(c.index(), Some(2));
+        assert_eq!(c.remove_current(), Some(10));
+        assert_eq!((c.index(), c.current().copied()), (Some(2), Some(15)));
+        *c.peek_prev().unwrap() += 1;
+        c.move_prev();
+        c.move_prev();
+        c.move_prev();
+        c.insert_after(-1);
+        c.insert_before(9);
+        assert_eq!(c.as_cursor().index(), None);
+        c.move_prev();
+        assert_eq!(c.remove_current(), Some(9));
+        assert_eq!(c.index(), None);
+        assert_eq!(to_vec(&l), [-1, 0, 6, 15, 2, 3]);
+    }
+
+    #[test]
+    fn elements_are_dropped_once() {
+        use std::rc::Rc;
+        let counter = Rc::new(());
+        let mut l: DList<Rc<()>> = (0..10).map(|_| counter.clone()).collect();
+        l.remove_at(3);
+        l.pop_back();
+        drop(l.cursor_front_mut().remove_current());
+        assert_eq!(Rc::strong_count(&counter), 8);
+        drop(l);
+        assert_eq!(Rc::strong_count(&counter), 1);
+    }
+
+    #[test]
+    fn handles() {
+        let mut l = DList::new();
+        let hs: Vec<_> = (0..4).map(|i| l.push_front_handle(i)).collect();
+        // SAFETY: all the handles come from l, and are used until their node is removed
+        unsafe {
+            assert_eq!(*l.get(hs[1]), 1);
+            *l.get_mut(hs[1]) = 10;
+            l.move_to_front(hs[0]);
+            l.move_to_front(hs[0]);
+            assert_eq!(to_vec(&l), [0, 3, 2, 10]);
+            l.move_to_front(hs[1]);
+            assert_eq!((to_vec(&l), l.peek_back()), (vec![10, 0, 3, 2], Some(&2)));
+            assert_eq!(l.remove(hs[3]), 3);
+            l.move_to_front(hs[2]);
+            assert_eq!(to_vec(&l), [2, 10, 0]);
+            assert_eq!(l.iter().rev().copied().collect::<Vec<_>>(), [0, 10, 2]);
+        }
+    }
+
+    #[test]
+    fn long_lists_drop_without_overflow() {
+        // Miri is slow: a shorter list is enough to check the frees
+        let n = if cfg!(miri) { 1_000 } else { 1_000_000 };
+        let l: DList<u32> = (0..n).collect();
+        assert_eq!(l.len(), n as usize);
+    }
+}
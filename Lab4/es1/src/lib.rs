@@ -0,0 +1,2 @@
+pub mod es0401;
+pub mod lru;
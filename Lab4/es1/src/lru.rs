@@ -0,0 +1,360 @@
+// *****
+// LRU cache: a HashMap from the keys to the nodes of a DList (the raw pointer one of
+// List4) that keeps the entries from the most to the least recently used.
+// A hit moves its node to the front, a new entry goes to the front and, when the cache
+// is full, the entry at the back is evicted: all O(1).
+//
+// The map holds handles to the nodes of the list. Every handle in the map belongs to
+// self.list and points to a node still in it: entries are removed from the map and the
+// list together, this is what makes the unsafe calls below sound. A node leaves the list
+// only once its handle has left the map, and the handle is found by identity if looking
+// up the key fails: a K with a Hash or Eq that doesn't agree with itself (which safe
+// code can write) may lose entries, but never leave a dangling handle behind.
+
+use std::borrow::Borrow;
+use std::collections::HashMap;
+use std::fmt;
+use std::hash::Hash;
+
+use crate::es0401::List4::{self, DList, Handle};
+
+type EvictFn<K, V> = Box<dyn FnMut(K, V) + Send>;
+
+pub struct LruCache<K, V> {
+    map: HashMap<K, Handle<(K, V)>>,
+    // most recently used at the front
+    list: DList<(K, V)>,
+    capacity: usize,
+    on_evict: Option<EvictFn<K, V>>,
+}
+
+// the handles are pointers into self.list, which the cache owns: it can be sent and
+// shared like the list and the map
+unsafe impl<K: Send, V: Send> Send for LruCache<K, V> {}
+unsafe impl<K: Sync, V: Sync> Sync for LruCache<K, V> {}
+
+impl<K: Hash + Eq + Clone, V> LruCache<K, V> {
+    // panics if capacity is 0
+    pub fn new(capacity: usize) -> Self {
+        assert!(capacity > 0, "LruCache: capacity must be positive");
+        LruCache { map: HashMap::with_capacity(capacity), list: DList::new(), capacity, on_evict: None }
+    }
+
+    // f is called with every entry evicted to make room for a new one, or by
+    // set_capacity; not with the entries removed by pop, pop_lru or clear
+    pub fn on_evict<F: FnMut(K, V) + Send + 'static>(mut self, f: F) -> Self {
+        self.on_evict = Some(Box::new(f));
+        self
+    }
+
+    pub fn len(&self) -> usize {
+        self.list.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.list.is_empty()
+    }
+
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    // evict the least recently used entries if there are more than capacity.
+    // panics if capacity is 0
+    pub fn set_capacity(&mut self, capacity: usize) {
+        assert!(capacity > 0, "LruCache: capacity must be positive");
+        self.capacity = capacity;
+        while self.len() > capacity {
+            self.evict();
+        }
+    }
+
+    pub fn contains_key<Q>(&self, k: &Q) -> bool
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        self.map.contains_key(k)
+    }
+
+    // the value of k, which becomes the most recently used
+    pub fn get<Q>(&mut self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let h = *self.map.get(k)?;
+        // SAFETY: the handles in the map are valid handles of self.list
+        unsafe {
+            self.list.move_to_front(h);
+            Some(&self.list.get(h).1)
+        }
+    }
+
+    pub fn get_mut<Q>(&mut self, k: &Q) -> Option<&mut V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let h = *self.map.get(k)?;
+        // SAFETY: as in get
+        unsafe {
+            self.list.move_to_front(h);
+            Some(&mut self.list.get_mut(h).1)
+        }
+    }
+
+    // the value of k, without changing the order
+    pub fn peek<Q>(&self, k: &Q) -> Option<&V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let h = *self.map.get(k)?;
+        // SAFETY: as in get
+        Some(unsafe { &self.list.get(h).1 })
+    }
+
+    // the least recently used entry, the next to be evicted
+    pub fn peek_lru(&self) -> Option<(&K, &V)> {
+        self.list.peek_back().map(|(k, v)| (k, v))
+    }
+
+    // insert or replace the value of k, which becomes the most recently used; returns the
+    // old value. A new key in a full cache evicts the least recently used entry
+    pub fn put(&mut self, k: K, v: V) -> Option<V> {
+        if let Some(&h) = self.map.get(&k) {
+            // SAFETY: as in get
+            unsafe {
+                self.list.move_to_front(h);
+                return Some(std::mem::replace(&mut self.list.get_mut(h).1, v));
+            }
+        }
+        if self.len() == self.capacity {
+            self.evict();
+        }
+        let h = self.list.push_front_handle((k.clone(), v));
+        self.map.insert(k, h);
+        None
+    }
+
+    pub fn pop<Q>(&mut self, k: &Q) -> Option<V>
+    where
+        K: Borrow<Q>,
+        Q: Hash + Eq + ?Sized,
+    {
+        let h = self.map.remove(k)?;
+        // SAFETY: as in get, and the handle has just left the map
+        Some(unsafe { self.list.remove(h).1 })
+    }
+
+    pub fn pop_lru(&mut self) -> Option<(K, V)> {
+        let h = self.list.back_handle()?;
+        // SAFETY: a handle of the back node, which is in the list
+        let k = unsafe { &self.list.get(h).0 };
+        match self.map.remove_entry(k) {
+            Some((_, found)) if found == h => {}
+            other => {
+                // the key found another entry or none: put that back, and take the
+                // handle out of the map the slow way
+                if let Some((k, found)) = other {
+                    self.map.insert(k, found);
+                }
+                self.map.retain(|_, &mut found| found != h);
+            }
+        }
+        // SAFETY: as in get, and the handle has just left the map
+        Some(unsafe { self.list.remove(h) })
+    }
+
+    pub fn clear(&mut self) {
+        self.map.clear();
+        self.list.clear();
+    }
+
+    // the entries from the most to the least recently used (rev() for the opposite)
+    pub fn iter(&self) -> Iter<'_, K, V> {
+        Iter(self.list.iter())
+    }
+
+    fn evict(&mut self) {
+        if let Some((k, v)) = self.pop_lru() {
+            if let Some(f) = &mut self.on_evict {
+                f(k, v);
+            }
+        }
+    }
+}
+
+impl<K: fmt::Debug, V: fmt::Debug> fmt::Debug for LruCache<K, V> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_map().entries(self.list.iter().map(|(k, v)| (k, v))).finish()
+    }
+}
+
+impl<'a, K: Hash + Eq + Clone, V> IntoIterator for &'a LruCache<K, V> {
+    type Item = (&'a K, &'a V);
+    type IntoIter = Iter<'a, K, V>;
+
+    fn into_iter(self) -> Iter<'a, K, V> {
+        self.iter()
+    }
+}
+
+pub struct Iter<'a, K, V>(List4::Iter<'a, (K, V)>);
+
+impl<'a, K, V> Iterator for Iter<'a, K, V> {
+    type Item = (&'a K, &'a V);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(k, v)| (k, v))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl<K, V> DoubleEndedIterator for Iter<'_, K, V> {
+    fn next_back(&mut self) -> Option<Self::Item> {
+        self.0.next_back().map(|(k, v)| (k, v))
+    }
+}
+
+impl<K, V> ExactSizeIterator for Iter<'_, K, V> {}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn keys(c: &LruCache<&'static str, i32>) -> Vec<&'static str> {
+        c.iter().map(|(k, _)| *k).collect()
+    }
+
+    #[test]
+    fn get_promotes_and_put_evicts() {
+        let mut c = LruCache::new(2);
+        assert_eq!(c.put("a", 1), None);
+        assert_eq!(c.put("b", 2), None);
+        assert_eq!(c.get("a"), Some(&1));
+        assert_eq!(keys(&c), ["a", "b"]);
+        c.put("c", 3);
+        assert_eq!(keys(&c), ["c", "a"]);
+        assert!(!c.contains_key("b"));
+        assert_eq!(c.get("b"), None);
+        assert_eq!((c.len(), c.capacity()), (2, 2));
+    }
+
+    #[test]
+    fn put_replaces_and_promotes() {
+        let mut c = LruCache::new(2);
+        c.put("a", 1);
+        c.put("b", 2);
+        assert_eq!(c.put("a", 10), Some(1));
+        c.put("c", 3);
+        assert_eq!(keys(&c), ["c", "a"]);
+        *c.get_mut("a").unwrap() += 1;
+        assert_eq!(c.peek("a"), Some(&11));
+    }
+
+    #[test]
+    fn peek_does_not_promote() {
+        let mut c = LruCache::new(2);
+        c.put("a", 1);
+        c.put("b", 2);
+        assert_eq!(c.peek("a"), Some(&1));
+        assert_eq!(c.peek_lru(), Some((&"a", &1)));
+        c.put("c", 3);
+        assert_eq!(keys(&c), ["c", "b"]);
+    }
+
+    #[test]
+    fn pop_and_pop_lru() {
+        let mut c = LruCache::new(3);
+        for (k, v) in [("a", 1), ("b", 2), ("c", 3)] {
+            c.put(k, v);
+        }
+        assert_eq!(c.pop("b"), Some(2));
+        assert_eq!(c.pop("b"), None);
+        assert_eq!(c.pop_lru(), Some(("a", 1)));
+        assert_eq!(c.iter().rev().map(|(k, _)| *k).collect::<Vec<_>>(), ["c"]);
+        c.clear();
+        assert!(c.is_empty() && c.pop_lru().is_none());
+        assert_eq!(format!("{:?}", c), "{}");
+    }
+
+    #[test]
+    fn eviction_callback() {
+        let evicted = Arc::new(Mutex::new(Vec::new()));
+        let log = evicted.clone();
+        let mut c = LruCache::new(3).on_evict(move |k, v| log.lock().unwrap().push((k, v)));
+        for (i, k) in ["a", "b", "c", "d", "e"].into_iter().enumerate() {
+            c.put(k, i);
+        }
+        c.pop_lru();
+        c.set_capacity(1);
+        assert_eq!(format!("{:?}", c), r#"{"e": 4}"#);
+        assert_eq!(*evicted.lock().unwrap(), [("a", 0), ("b", 1), ("d", 3)]);
+    }
+
+    #[test]
+    fn string_keys_looked_up_by_str() {
+        let mut c: LruCache<String, Vec<u8>> = LruCache::new(8);
+        c.put("x".to_string(), vec![1]);
+        assert_eq!(c.get("x").map(Vec::len), Some(1));
+    }
+
+    #[test]
+    fn entries_are_dropped_once() {
+        let counter = Arc::new(());
+        let mut c = LruCache::new(4);
+        for i in 0..10 {
+            c.put(i, counter.clone());
+        }
+        c.put(9, counter.clone());
+        c.pop(&8);
+        assert_eq!(Arc::strong_count(&counter), 4);
+        drop(c);
+        assert_eq!(Arc::strong_count(&counter), 1);
+    }
+
+    // equal by value, but hashed differently every time: the map can't find it again
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Flaky(u8);
+
+    impl Hash for Flaky {
+        fn hash<H: std::hash::Hasher>(&self, state: &mut H) {
+            use std::sync::atomic::{AtomicU64, Ordering};
+            static CALLS: AtomicU64 = AtomicU64::new(0);
+            CALLS.fetch_add(1, Ordering::Relaxed).hash(state);
+        }
+    }
+
+    #[test]
+    fn inconsistent_keys_leave_no_dangling_handles() {
+        let mut c = LruCache::new(2);
+        for i in 0..6 {
+            c.put(Flaky(i % 3), i);
+            // whatever the map finds, it's a live entry (Miri checks the accesses)
+            for k in 0..3 {
+                c.get(&Flaky(k));
+                c.peek(&Flaky(k));
+            }
+        }
+        while c.pop_lru().is_some() {}
+        assert!(c.map.is_empty());
+        for k in 0..3 {
+            assert_eq!(c.get(&Flaky(k)), None);
+            assert_eq!(c.pop(&Flaky(k)), None);
+        }
+        c.put(Flaky(0), 9);
+        assert_eq!(c.iter().map(|(_, v)| *v).collect::<Vec<_>>(), [9]);
+    }
+
+    #[test]
+    #[should_panic]
+    fn zero_capacity() {
+        LruCache::<u8, u8>::new(0);
+    }
+}
@@ -1,6 +1,6 @@
-use crate::es0401::{List1,List2,List3};
+use es1::es0401::{List1,List2,List3};
+use es1::lru::LruCache;
 
-mod es0401;
 fn main() {
     let mut l1 = List1::List::<i32>::new();
     let mut l2 = List2::List::<i32>::new();
@@ -21,6 +21,12 @@ fn main() {
     println!("push: 3 -> {:?}", l1);
     println!("Take 2 -> New {:?}; Old {:?}", l1.take(2), l1);
 
+    println!("==========TESTING PLIST=========");
+    let v1 = List1::PList::new().prepend(1).prepend(2);
+    let v2 = v1.prepend(3);
+    println!("v1 {:?}, v2 = v1.prepend(3) {:?}, v2.tail() {:?}", v1, v2, v2.tail());
+    println!("v2.tail() shares v1: {}", v2.tail().ptr_eq(&v1));
+
     println!("==========TESTING LIST2=========");
     println!("{:?}", l2);
     println!("pop {:?} -> {:?}",l2.pop(), l2);
@@ -35,6 +41,14 @@ fn main() {
     l2.push(3);
     println!("push: 3 -> {:?}", l2);
     println!("Take 2 -> New {:?}; Old {:?}", l2.take(2), l2);
+    l2.extend([4, 5, 6]);
+    println!("extend [4, 5, 6] -> {:?} (len {})", l2, l2.len());
+    l2.reverse();
+    println!("reverse -> {:?}", l2);
+    l2.retain(|x| x % 2 == 0);
+    println!("retain even -> {:?}", l2);
+    let tail = l2.split_off(1);
+    println!("split_off 1 -> {:?} {:?}", l2, tail);
 
     println!("==========TESTING LIST3=========");
     println!("{:?}", l3);
@@ -50,6 +64,20 @@ fn main() {
     println!("push_back: 0 -> {:?}", l3);
     println!("pop_front: {:?} -> {:?}", l3.pop_front(), l3);
     println!("pop_back: {:?} -> {:?}", l3.pop_back(), l3);
-    println!("pop n=2: {:?} -> {:?}", l3.popn(2), l3);
-}
+    println!("remove_at 2: {:?} -> {:?}", l3.remove_at(2), l3);
+    l3.insert_at(1, 5);
+    println!("insert_at 1: 5 -> {:?}", l3);
+    let mut cursor = l3.cursor_back_mut();
+    cursor.insert_before(6);
+    cursor.move_prev();
+    println!("cursor at {:?}: removed {:?} -> {:?}", cursor.index(), cursor.remove_current(), l3);
 
+    println!("==========TESTING LRU=========");
+    let mut cache = LruCache::new(2).on_evict(|k, v| println!("evicted {} -> {}", k, v));
+    cache.put("a", 1);
+    cache.put("b", 2);
+    let a = cache.get("a").copied();
+    println!("get a: {:?} -> {:?}", a, cache);
+    cache.put("c", 3);
+    println!("put c -> {:?}", cache);
+}
@@ -0,0 +1,97 @@
+// Random sequences of operations on both DList implementations, checked against VecDeque.
+// Under Miri (cargo +nightly miri test --test dlist) fewer and shorter cases are run.
+
+use std::collections::VecDeque;
+
+use es1::es0401::{List3, List4};
+use proptest::prelude::*;
+use proptest::test_runner::Config;
+
+#[derive(Debug, Clone)]
+enum Op {
+    PushFront(i32),
+    PushBack(i32),
+    PopFront,
+    PopBack,
+    InsertAt(usize, i32),
+    RemoveAt(usize),
+    // walk a mutable cursor by the given steps, then insert or remove there
+    CursorInsert(usize, i32),
+    CursorRemove(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    prop_oneof![
+        any::<i32>().prop_map(Op::PushFront),
+        any::<i32>().prop_map(Op::PushBack),
+        Just(Op::PopFront),
+        Just(Op::PopBack),
+        (0..20usize, any::<i32>()).prop_map(|(i, x)| Op::InsertAt(i, x)),
+        (0..20usize).prop_map(Op::RemoveAt),
+        (0..20usize, any::<i32>()).prop_map(|(i, x)| Op::CursorInsert(i, x)),
+        (0..20usize).prop_map(Op::CursorRemove),
+    ]
+}
+
+fn config() -> Config {
+    if cfg!(miri) {
+        // no file system under Miri's isolation: don't save the failing cases
+        Config { cases: 8, failure_persistence: None, ..Config::default() }
+    } else {
+        Config::default()
+    }
+}
+
+// the two lists have the same API, but List3 lends its elements through Ref guards
+// and iterates over clones
+macro_rules! check_against_vecdeque {
+    ($name:ident, $list:ty, $peek:expr, $item:expr) => {
+        proptest! {
+            #![proptest_config(config())]
+            #[test]
+            fn $name(ops in prop::collection::vec(op(), 0..100)) {
+                let mut list = <$list>::new();
+                let mut model = VecDeque::new();
+                for op in ops {
+                    match op {
+                        Op::PushFront(x) => { list.push_front(x); model.push_front(x); }
+                        Op::PushBack(x) => { list.push_back(x); model.push_back(x); }
+                        Op::PopFront => prop_assert_eq!(list.pop_front(), model.pop_front()),
+                        Op::PopBack => prop_assert_eq!(list.pop_back(), model.pop_back()),
+                        Op::InsertAt(i, x) => {
+                            let i = i.min(model.len());
+                            list.insert_at(i, x);
+                            model.insert(i, x);
+                        }
+                        Op::RemoveAt(i) => prop_assert_eq!(list.remove_at(i), model.remove(i)),
+                        Op::CursorInsert(steps, x) => {
+                            // steps past the back land on the ghost, which inserts at the back
+                            let mut c = list.cursor_front_mut();
+                            for _ in 0..steps.min(model.len()) {
+                                c.move_next();
+                            }
+                            c.insert_before(x);
+                            model.insert(steps.min(model.len()), x);
+                        }
+                        Op::CursorRemove(steps) => {
+                            let mut c = list.cursor_front_mut();
+                            for _ in 0..steps {
+                                c.move_next();
+                            }
+                            let expected = if model.is_empty() { None } else { model.remove(steps % (model.len() + 1)) };
+                            prop_assert_eq!(c.remove_current(), expected);
+                        }
+                    }
+                    prop_assert_eq!(list.len(), model.len());
+                    prop_assert_eq!(list.peek_front().map($peek), model.front().copied());
+                    prop_assert_eq!(list.peek_back().map($peek), model.back().copied());
+                }
+                prop_assert_eq!(list.iter().map($item).collect::<Vec<_>>(), model.iter().copied().collect::<Vec<_>>());
+                prop_assert_eq!(list.iter().rev().map($item).collect::<Vec<_>>(), model.iter().rev().copied().collect::<Vec<_>>());
+            }
+        }
+    };
+}
+
+check_against_vecdeque!(rc_list_behaves_like_vecdeque, List3::DList<i32>, |x| *x, |x| x);
+check_against_vecdeque!(raw_list_behaves_like_vecdeque, List4::DList<i32>, |x| *x, |x| *x);
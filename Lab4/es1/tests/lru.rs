@@ -0,0 +1,72 @@
+// Random sequences of operations on LruCache, checked against a Vec of entries kept in
+// recency order (most recent first).
+
+use es1::lru::LruCache;
+use proptest::prelude::*;
+
+#[derive(Debug, Clone)]
+enum Op {
+    Get(u8),
+    Peek(u8),
+    Put(u8, i32),
+    Pop(u8),
+    PopLru,
+    SetCapacity(usize),
+}
+
+fn op() -> impl Strategy<Value = Op> {
+    // few keys, so that hits and evictions are frequent
+    let key = 0..12u8;
+    prop_oneof![
+        key.clone().prop_map(Op::Get),
+        key.clone().prop_map(Op::Peek),
+        (key.clone(), any::<i32>()).prop_map(|(k, v)| Op::Put(k, v)),
+        key.prop_map(Op::Pop),
+        Just(Op::PopLru),
+        (1..8usize).prop_map(Op::SetCapacity),
+    ]
+}
+
+proptest! {
+    #[test]
+    fn behaves_like_a_recency_ordered_vec(capacity in 1..8usize, ops in prop::collection::vec(op(), 0..200)) {
+        let mut cache = LruCache::new(capacity);
+        let mut model: Vec<(u8, i32)> = Vec::new();
+        let mut capacity = capacity;
+        for op in ops {
+            match op {
+                Op::Get(k) => {
+                    let expected = model.iter().position(|e| e.0 == k).map(|i| {
+                        let e = model.remove(i);
+                        model.insert(0, e);
+                        e.1
+                    });
+                    prop_assert_eq!(cache.get(&k).copied(), expected);
+                }
+                Op::Peek(k) => {
+                    let expected = model.iter().find(|e| e.0 == k).map(|e| e.1);
+                    prop_assert_eq!(cache.peek(&k).copied(), expected);
+                }
+                Op::Put(k, v) => {
+                    let old = model.iter().position(|e| e.0 == k).map(|i| model.remove(i).1);
+                    if old.is_none() && model.len() == capacity {
+                        model.pop();
+                    }
+                    model.insert(0, (k, v));
+                    prop_assert_eq!(cache.put(k, v), old);
+                }
+                Op::Pop(k) => {
+                    let expected = model.iter().position(|e| e.0 == k).map(|i| model.remove(i).1);
+                    prop_assert_eq!(cache.pop(&k), expected);
+                }
+                Op::PopLru => prop_assert_eq!(cache.pop_lru(), model.pop()),
+                Op::SetCapacity(c) => {
+                    capacity = c;
+                    model.truncate(c);
+                    cache.set_capacity(c);
+                }
+            }
+            prop_assert_eq!(cache.iter().map(|(k, v)| (*k, *v)).collect::<Vec<_>>(), model.clone());
+        }
+    }
+}
@@ -0,0 +1,82 @@
+// cargo bench --bench solve
+//
+// The streaming search of Puzzle::search against the first version of the solver, which
+// collected the whole product permutations x operators with collect_vec and split it into
+// 4 fixed chunks, one per thread. Same candidates (left to right evaluation, all the
+// operator sequences), so the times are comparable; the old version also needs memory for
+// every candidate before starting.
+
+use std::collections::HashSet;
+use std::hint::black_box;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use es1::{Op, Puzzle};
+use itertools::Itertools;
+
+fn time<F: FnMut() -> usize>(mut f: F) -> (Duration, usize) {
+    let start = Instant::now();
+    let n = black_box(f());
+    (start.elapsed(), n)
+}
+
+// the old main, with the operators of the library
+fn collect_and_chunk(numbers: &[i64], target: i64) -> usize {
+    let num_threads = 4;
+    let perm = numbers.iter().permutations(numbers.len()).collect_vec();
+    let ops = itertools::repeat_n(Op::ALL.iter(), numbers.len() - 1).multi_cartesian_product().collect_vec();
+    let brute_force = perm.iter().cartesian_product(ops.iter()).collect_vec();
+    let chunk = brute_force.len() / num_threads;
+    let results: HashSet<String> = thread::scope(|s| {
+        let threads: Vec<_> = (0..num_threads)
+            .map(|i| {
+                let end = if i == num_threads - 1 { brute_force.len() } else { (i + 1) * chunk };
+                let slice = &brute_force[i * chunk..end];
+                s.spawn(move || {
+                    slice
+                        .iter()
+                        .filter_map(|(nums, ops)| {
+                            let mut value = *nums[0];
+                            let mut text = value.to_string();
+                            for (op, &&n) in ops.iter().zip(&nums[1..]) {
+                                value = op.apply(value, n)?;
+                                text.push_str(&format!(" {} {}", op.symbol(), n));
+                            }
+                            (value == target).then_some(text)
+                        })
+                        .collect::<Vec<_>>()
+                })
+            })
+            .collect();
+        threads.into_iter().flat_map(|t| t.join().unwrap()).collect()
+    });
+    results.len()
+}
+
+fn row(name: &str, old: Duration, new: (Duration, usize)) {
+    let speedup = old.as_secs_f64() / new.0.as_secs_f64();
+    println!("{:<32} {:>12.2?} {:>8} sol. {:>8.1}x", name, new.0, new.1, speedup);
+}
+
+fn main() {
+    let cores = thread::available_parallelism().map_or(1, |n| n.get());
+    println!("{} core disponibili\n", cores);
+
+    for numbers in [vec![7, 2, 1, 2, 2, 3], vec![1, 2, 3, 4, 5, 6, 7]] {
+        let target = 10;
+        let puzzle = Puzzle::new(numbers.clone(), target);
+        let candidates = (1..=numbers.len()).product::<usize>() * 4usize.pow(numbers.len() as u32 - 1);
+        println!("{:?} -> {}: {} candidati", numbers, target, candidates);
+
+        let old = time(|| collect_and_chunk(&numbers, target));
+        println!("{:<32} {:>12.2?} {:>8} sol. (non canoniche)", "collect_vec, 4 blocchi fissi", old.0, old.1);
+        let mut threads = vec![1, 2, 4, cores];
+        threads.sort();
+        threads.dedup();
+        for t in threads {
+            row(&format!("work stealing, {} thread", t), old.0, time(|| puzzle.search().threads(t).run().len()));
+        }
+        row("prima soluzione", old.0, time(|| puzzle.search().first(true).run().len()));
+        println!();
+    }
+}
@@ -0,0 +1,262 @@
+// Arithmetic expressions over the numbers of the puzzle: the four operators, the trees
+// they build and their canonical form, used to recognise the same solution written in
+// different ways.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+use crate::number::Number;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Op {
+    Add,
+    Sub,
+    Mul,
+    Div,
+}
+
+impl Op {
+    pub const ALL: [Op; 4] = [Op::Add, Op::Sub, Op::Mul, Op::Div];
+
+    pub fn symbol(self) -> char {
+        match self {
+            Op::Add => '+',
+            Op::Sub => '-',
+            Op::Mul => 'x',
+            Op::Div => '/',
+        }
+    }
+
+    // '*' is accepted for 'x' as well
+    pub fn from_symbol(c: char) -> Option<Op> {
+        match c {
+            '+' => Some(Op::Add),
+            '-' => Some(Op::Sub),
+            'x' | '*' => Some(Op::Mul),
+            '/' => Some(Op::Div),
+            _ => None,
+        }
+    }
+
+    pub fn precedence(self) -> u8 {
+        match self {
+            Op::Add | Op::Sub => 1,
+            Op::Mul | Op::Div => 2,
+        }
+    }
+
+    // None on overflow and division by zero; with i64 also for divisions with a remainder
+    pub fn apply<N: Number>(self, a: N, b: N) -> Option<N> {
+        match self {
+            Op::Add => a.try_add(b),
+            Op::Sub => a.try_sub(b),
+            Op::Mul => a.try_mul(b),
+            Op::Div => a.try_div(b),
+        }
+    }
+}
+
+impl fmt::Display for Op {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}", self.symbol())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Expr {
+    Num(i64),
+    Bin(Op, Box<Expr>, Box<Expr>),
+}
+
+impl Expr {
+    pub fn bin(op: Op, l: Expr, r: Expr) -> Expr {
+        Expr::Bin(op, Box::new(l), Box::new(r))
+    }
+
+    // the value with integer arithmetic
+    pub fn eval(&self) -> Option<i64> {
+        self.eval_in()
+    }
+
+    // the value in any kind of number: eval_in::<Rational>() for exact fractions
+    pub fn eval_in<N: Number>(&self) -> Option<N> {
+        match self {
+            Expr::Num(n) => Some(N::from(*n)),
+            Expr::Bin(op, l, r) => op.apply(l.eval_in()?, r.eval_in()?),
+        }
+    }
+
+    // the same string for all the expressions that differ only by the order of the terms
+    // of a sum or of the factors of a product, or by the way these are grouped:
+    // "2 x (3 + 1)" and "(1 + 3) x 2" both give "2 x (1 + 3)", "7 - (2 - 5)" gives
+    // "5 + 7 - 2"
+    pub fn canonical(&self) -> String {
+        Canon::from(self).to_string()
+    }
+
+    fn precedence(&self) -> u8 {
+        match self {
+            Expr::Num(_) => 3,
+            Expr::Bin(op, _, _) => op.precedence(),
+        }
+    }
+}
+
+// with the usual precedence, and only the parentheses that change the value:
+// a - (b - c) keeps them, a + (b - c) doesn't need them
+impl fmt::Display for Expr {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Expr::Num(n) => write!(f, "{}", n),
+            Expr::Bin(op, l, r) => {
+                let p = op.precedence();
+                if l.precedence() < p {
+                    write!(f, "({})", l)?;
+                } else {
+                    write!(f, "{}", l)?;
+                }
+                write!(f, " {} ", op)?;
+                let right_parens = r.precedence() < p || (r.precedence() == p && matches!(op, Op::Sub | Op::Div));
+                if right_parens {
+                    write!(f, "({})", r)
+                } else {
+                    write!(f, "{}", r)
+                }
+            }
+        }
+    }
+}
+
+// Sums are flattened into the terms added and subtracted, products into the factors
+// multiplied and divided, each list sorted: numbers first by value, then the others
+// by their text.
+enum Canon {
+    Num(i64),
+    Sum(Vec<Canon>, Vec<Canon>),
+    Prod(Vec<Canon>, Vec<Canon>),
+}
+
+impl Canon {
+    fn from(e: &Expr) -> Canon {
+        let (mut plus, mut minus) = (Vec::new(), Vec::new());
+        match e {
+            Expr::Num(n) => Canon::Num(*n),
+            Expr::Bin(Op::Add | Op::Sub, _, _) => {
+                Canon::flatten(e, true, &[Op::Add, Op::Sub], &mut plus, &mut minus);
+                sort(&mut plus);
+                sort(&mut minus);
+                Canon::Sum(plus, minus)
+            }
+            Expr::Bin(Op::Mul | Op::Div, _, _) => {
+                Canon::flatten(e, true, &[Op::Mul, Op::Div], &mut plus, &mut minus);
+                sort(&mut plus);
+                sort(&mut minus);
+                Canon::Prod(plus, minus)
+            }
+        }
+    }
+
+    // ops[0] keeps the side of its right operand, ops[1] switches it
+    fn flatten(e: &Expr, direct: bool, ops: &[Op; 2], plus: &mut Vec<Canon>, minus: &mut Vec<Canon>) {
+        match e {
+            Expr::Bin(op, l, r) if ops.contains(op) => {
+                Canon::flatten(l, direct, ops, plus, minus);
+                Canon::flatten(r, direct == (*op == ops[0]), ops, plus, minus);
+            }
+            _ if direct => plus.push(Canon::from(e)),
+            _ => minus.push(Canon::from(e)),
+        }
+    }
+}
+
+fn sort(v: &mut [Canon]) {
+    v.sort_by(|a, b| match (a, b) {
+        (Canon::Num(x), Canon::Num(y)) => x.cmp(y),
+        (Canon::Num(_), _) => Ordering::Less,
+        (_, Canon::Num(_)) => Ordering::Greater,
+        _ => a.to_string().cmp(&b.to_string()),
+    });
+}
+
+impl fmt::Display for Canon {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let (plus, minus, ops) = match self {
+            Canon::Num(n) => return write!(f, "{}", n),
+            Canon::Sum(plus, minus) => (plus, minus, [Op::Add, Op::Sub]),
+            Canon::Prod(plus, minus) => (plus, minus, [Op::Mul, Op::Div]),
+        };
+        // a subtraction with nothing added: the first term is 0 - x, which the puzzles
+        // can't build, but it costs nothing to print it right
+        if plus.is_empty() {
+            write!(f, "0")?;
+        }
+        let items = plus.iter().map(|c| (ops[0], c)).chain(minus.iter().map(|c| (ops[1], c)));
+        for (i, (op, c)) in items.enumerate() {
+            if i > 0 || plus.is_empty() {
+                write!(f, " {} ", op)?;
+            }
+            // the terms of a sum are numbers or products; the factors of a product need
+            // parentheses around sums
+            match (self, c) {
+                (Canon::Prod(..), Canon::Sum(..)) => write!(f, "({})", c)?,
+                _ => write!(f, "{}", c)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::number::Rational;
+
+    fn n(x: i64) -> Expr {
+        Expr::Num(x)
+    }
+
+    #[test]
+    fn division_by_zero_and_with_remainder() {
+        assert_eq!(Op::Div.apply(2, 0), None);
+        assert_eq!(Op::Div.apply(2, 3), None);
+        assert_eq!(Op::Div.apply(6, 3), Some(2));
+        assert_eq!(Op::Div.apply(i64::MIN, -1), None);
+        assert_eq!(Op::Mul.apply(i64::MAX, 2), None);
+        assert_eq!(Op::Div.apply(Rational::from(2), 3.into()), Rational::new(2, 3));
+    }
+
+    #[test]
+    fn display_with_minimal_parentheses() {
+        // (((2 - 7) - 2) x 2) + 1
+        let e = Expr::bin(Op::Add, Expr::bin(Op::Mul, Expr::bin(Op::Sub, Expr::bin(Op::Sub, n(2), n(7)), n(2)), n(2)), n(1));
+        assert_eq!(e.to_string(), "(2 - 7 - 2) x 2 + 1");
+        assert_eq!(e.eval(), Some(-13));
+        let e = Expr::bin(Op::Sub, n(8), Expr::bin(Op::Sub, n(3), n(1)));
+        assert_eq!(e.to_string(), "8 - (3 - 1)");
+        let e = Expr::bin(Op::Add, n(8), Expr::bin(Op::Sub, n(3), n(1)));
+        assert_eq!(e.to_string(), "8 + 3 - 1");
+        let e = Expr::bin(Op::Div, n(8), Expr::bin(Op::Mul, n(2), n(2)));
+        assert_eq!(e.to_string(), "8 / (2 x 2)");
+        // 8 / 3 x 3 is 8 only with fractions
+        let e = Expr::bin(Op::Mul, Expr::bin(Op::Div, n(8), n(3)), n(3));
+        assert_eq!((e.eval(), e.eval_in::<Rational>()), (None, Some(8.into())));
+    }
+
+    #[test]
+    fn canonical_forms() {
+        let a = Expr::bin(Op::Mul, n(2), Expr::bin(Op::Add, n(3), n(1)));
+        let b = Expr::bin(Op::Mul, Expr::bin(Op::Add, n(1), n(3)), n(2));
+        let c = Expr::bin(Op::Mul, Expr::bin(Op::Sub, n(3), Expr::bin(Op::Sub, n(0), n(1))), n(2));
+        assert_eq!(a.canonical(), "2 x (1 + 3)");
+        assert_eq!(b.canonical(), a.canonical());
+        assert_ne!(c.canonical(), a.canonical());
+        // a - (b - c) = a + c - b
+        let d = Expr::bin(Op::Sub, n(7), Expr::bin(Op::Sub, n(2), n(5)));
+        assert_eq!(d.canonical(), "5 + 7 - 2");
+        // a / (b / c) = a x c / b
+        let e = Expr::bin(Op::Div, n(12), Expr::bin(Op::Div, n(6), n(3)));
+        assert_eq!(e.canonical(), "3 x 12 / 6");
+        let f = Expr::bin(Op::Sub, Expr::bin(Op::Sub, n(10), n(2)), Expr::bin(Op::Mul, n(2), Expr::bin(Op::Add, n(1), n(1))));
+        assert_eq!(f.canonical(), "10 - 2 - 2 x (1 + 1)");
+    }
+}
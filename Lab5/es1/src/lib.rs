@@ -0,0 +1,8 @@
+pub mod expr;
+pub mod number;
+pub mod pool;
+pub mod solver;
+
+pub use expr::{Expr, Op};
+pub use number::{Number, Rational};
+pub use solver::{Arithmetic, Mode, Progress, Puzzle, Search, SolveReport, MAX_NUMBERS, MAX_TREE_NUMBERS};
@@ -1,110 +1,253 @@
-use clap::Parser;
-use itertools::Itertools;
-use std::{collections::HashSet,thread, time::Instant};
+use clap::error::ErrorKind;
+use clap::{Args, CommandFactory, Parser, Subcommand, ValueEnum};
+use es1::{Arithmetic, Mode, Op, Puzzle, SolveReport};
+use serde_json::json;
+use std::io::Write;
+use std::thread;
+use std::time::Duration;
 
 #[derive(Parser, Debug)]
-struct Args {
-    seq: String,
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
+struct Cli {
+    #[command(subcommand)]
+    command: Option<Command>,
+    #[command(flatten)]
+    solve: SolveArgs,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// risolvere lo stesso puzzle con 1, 2, ... N thread e stampare la scalabilità
+    Bench(BenchArgs),
+}
 
+#[derive(Args, Debug)]
+struct PuzzleArgs {
+    /// numeri da combinare, separati da spazi o virgole: "7 2 1 2 2"
+    #[arg(required = true)]
+    seq: Vec<String>,
+    /// valore da ottenere
+    #[arg(short, long, default_value_t = 10)]
+    target: i64,
+    /// operatori ammessi, tra + - x /
+    #[arg(short, long, default_value = "+-x/")]
+    ops: String,
+    /// valutazione: left (da sinistra a destra), precedence (x e / prima di + e -),
+    /// trees (tutte le parentesizzazioni)
+    #[arg(short, long, default_value_t = Mode::LeftToRight)]
+    mode: Mode,
+    /// aritmetica: integer (solo divisioni esatte) o rational (frazioni esatte)
+    #[arg(short, long, default_value_t = Arithmetic::Integer)]
+    arithmetic: Arithmetic,
+    /// formato dell'output
+    #[arg(short, long, value_enum, default_value_t = Format::Text)]
+    format: Format,
+}
 
-fn main() {
-    let op = vec!['+','-','x','/'];
-    let args = Args::parse();
-    let seq: Vec<_>= args.seq.rsplit(' ').collect();
-    let v2: Vec<i32> = seq.iter()
-        .map(|s| s.chars().next().expect("No value").to_digit(10).unwrap() as i32)
-        .collect();
+#[derive(Args, Debug)]
+struct SolveArgs {
+    #[command(flatten)]
+    puzzle: PuzzleArgs,
+    /// thread da usare, di default tutti quelli disponibili
+    #[arg(short = 'j', long)]
+    threads: Option<usize>,
+    /// fermarsi alla prima soluzione trovata
+    #[arg(long)]
+    first: bool,
+    /// mostrare l'avanzamento della ricerca su stderr
+    #[arg(long)]
+    progress: bool,
+}
 
-    let perm = v2.iter().permutations(5).collect_vec();
-    let comb = op.iter().combinations_with_replacement(4).collect_vec();
-    
-    // let brute_force = perm.iter().flat_map(|s| comb.iter().map(|o| (s,o))).collect(); // using iterator
-    let brute_force = perm.iter().cartesian_product(comb.iter()).collect_vec();
-    
-    let num_threads = 4;
-    
-    let start = Instant::now();
-    let t_results = thread::scope(|s| {
-        let mut threads = vec![]; 
-        
-        for i in 0..num_threads{
-            let slice = &brute_force[i*(brute_force.len()/num_threads)..if i==num_threads-1 {brute_force.len()}else {(i+1)*(brute_force.len()/num_threads)}];
-            threads.push(s.spawn(|| {
-                solve(slice)
-            }));
-        }
-        let mut results = HashSet::new();
-        for t in threads {
-            for res in t.join().unwrap(){
-                results.insert(res);
-            }
-        }
-        results
-    });
+#[derive(Args, Debug)]
+struct BenchArgs {
+    #[command(flatten)]
+    puzzle: PuzzleArgs,
+    /// numero massimo di thread, di default quelli disponibili
+    #[arg(short = 'j', long)]
+    max_threads: Option<usize>,
+    /// ripetizioni per ogni numero di thread, si tiene il tempo migliore
+    #[arg(short, long, default_value_t = 3)]
+    runs: usize,
+}
 
-    println!("Stringa: {}\nNum_threads: {}\nNum_sol: {}\nTime: {} ms\nSol: {:?}",args.seq, num_threads, t_results.len(), start.elapsed().as_millis(), t_results);
-    
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+enum Format {
+    Text,
+    Json,
+    Csv,
 }
 
-fn compute_operation(str: (&Vec<&i32>,&Vec<&char>)) -> Option<(i32,String)> {
-    let (seq,op) = str;
-    let mut init_value = *seq[0];
-    let mut initial_string = seq[0].to_string();
-    for (i,value) in seq[1..].iter().enumerate(){
-        initial_string.push_str(format!(" {} {}",op[i],value).as_str());
-        match op[i] {
-            '+' => init_value += **value,
-            '-' => init_value -= **value,
-            'x' => init_value *= **value,
-            '/' => {if **value == 0 || init_value % **value != 0 { return None;} else {init_value /= **value}},
-             _ => panic!("Operazione non supportata"),
-        }
+fn parse_numbers(s: &str) -> Result<Vec<i64>, String> {
+    s.split(|c: char| c.is_whitespace() || c == ',')
+        .filter(|t| !t.is_empty())
+        .map(|t| t.parse::<u32>().map(i64::from).map_err(|_| format!("numero non valido: {:?}", t)))
+        .collect()
+}
+
+fn parse_ops(s: &str) -> Result<Vec<Op>, String> {
+    let ops: Vec<Op> = s
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .map(|c| Op::from_symbol(c).ok_or(format!("operazione non supportata: {:?}", c)))
+        .collect::<Result<_, _>>()?;
+    if ops.is_empty() {
+        return Err("nessuna operazione".to_string());
     }
-    Some((init_value,initial_string))
+    Ok(ops)
 }
 
-fn solve(slice: &[(&Vec<&i32>, &Vec<&char>)]) -> Vec<String>{
-    let mut col: Vec<String> = vec![];
-    for str in slice {
-        let res = compute_operation(*str);
-        if res.is_some(){
-            let (value, string) = res.unwrap();
-            if value == 10 {
-                col.push(string);
-            }
+impl PuzzleArgs {
+    // exits with the usage error if the numbers or the operators can't be parsed, or if
+    // there are too many numbers to search
+    fn puzzle(&self) -> Puzzle {
+        // a single argument "7 2 1 2 2" and separate arguments 7 2 1 2 2 are the same
+        let numbers = self.seq.iter().map(|s| parse_numbers(s)).collect::<Result<Vec<_>, _>>();
+        let (numbers, ops) = match (numbers, parse_ops(&self.ops)) {
+            (Ok(numbers), Ok(ops)) => (numbers.concat(), ops),
+            (Err(e), _) | (_, Err(e)) => Cli::command().error(ErrorKind::ValueValidation, e).exit(),
+        };
+        let max = self.mode.max_numbers();
+        if numbers.len() > max {
+            let e = format!("troppi numeri: {}, al massimo {} in modalità {}", numbers.len(), max, self.mode);
+            Cli::command().error(ErrorKind::ValueValidation, e).exit();
         }
+        Puzzle::new(numbers, self.target).ops(ops).mode(self.mode).arithmetic(self.arithmetic)
     }
-    col
 }
 
-#[cfg(test)]
-mod tests {
-    use super::*;
+// quoted only if needed, as in RFC 4180
+fn csv_field(s: &str) -> String {
+    if s.contains([',', '"', '\n']) {
+        format!("\"{}\"", s.replace('"', "\"\""))
+    } else {
+        s.to_string()
+    }
+}
+
+fn millis(d: Duration) -> f64 {
+    d.as_secs_f64() * 1000.0
+}
 
-    #[test]
-    fn test_division_zero() {
-        let result = compute_operation((&vec![&2,&0,&2,&2,&1], &vec![&'/',&'-',&'x',&'+']));
-        assert!(result.is_none());
+fn solve(args: &SolveArgs) {
+    let puzzle = args.puzzle.puzzle();
+    let mut search = puzzle.search().first(args.first);
+    if let Some(threads) = args.threads {
+        search = search.threads(threads);
+    }
+    if args.progress {
+        search = search.progress(|p| {
+            let percent = p.done * 100 / p.total;
+            eprint!("\r{:3}% ({}/{} permutazioni, {} soluzioni)", percent, p.done, p.total, p.found);
+            let _ = std::io::stderr().flush();
+        });
+    }
+    let report = search.report();
+    if args.progress {
+        eprintln!();
     }
 
-    #[test]
-    fn test_not_integer_division() {
-        let result = compute_operation((&vec![&2,&3,&2,&2,&1], &vec![&'/',&'-',&'x',&'+']));
-        assert!(result.is_none());
+    let p = &args.puzzle;
+    match p.format {
+        Format::Text => {
+            println!(
+                "Numeri: {:?}\nTarget: {}\nModalità: {}\nAritmetica: {}\nThreads: {}\nEsplorati: {}\nNum_sol: {}\nTime: {:.3} ms",
+                puzzle.numbers(),
+                puzzle.target(),
+                p.mode,
+                p.arithmetic,
+                report.threads,
+                report.explored,
+                report.solutions.len(),
+                millis(report.elapsed)
+            );
+            for s in &report.solutions {
+                println!("{}", s);
+            }
+        }
+        Format::Json => {
+            let out = json!({
+                "numbers": puzzle.numbers(),
+                "target": puzzle.target(),
+                "mode": p.mode.to_string(),
+                "arithmetic": p.arithmetic.to_string(),
+                "threads": report.threads,
+                "explored": report.explored,
+                "elapsed_ms": millis(report.elapsed),
+                "solutions": report.solutions,
+            });
+            println!("{}", serde_json::to_string_pretty(&out).unwrap());
+        }
+        Format::Csv => {
+            println!("solution");
+            for s in &report.solutions {
+                println!("{}", csv_field(s));
+            }
+        }
     }
+}
 
-    #[test]
-    fn test_operation_negative() {
-        let result = compute_operation((&vec![&2,&7,&2,&2,&1], &vec![&'-',&'-',&'x',&'+']));
-        assert_eq!(result.unwrap(), (-13, "2 - 7 - 2 x 2 + 1".to_string()));
+fn bench(args: &BenchArgs) {
+    let puzzle = args.puzzle.puzzle();
+    let max = args.max_threads.unwrap_or_else(|| thread::available_parallelism().map_or(1, |n| n.get())).max(1);
+    // the best of the runs for every number of threads: the least disturbed by the rest
+    // of the machine
+    let reports: Vec<SolveReport> = (1..=max)
+        .map(|threads| {
+            (0..args.runs.max(1)).map(|_| puzzle.search().threads(threads).report()).min_by_key(|r| r.elapsed).unwrap()
+        })
+        .collect();
+    if let Some(r) = reports.iter().find(|r| r.solutions != reports[0].solutions) {
+        eprintln!("error: soluzioni diverse con {} thread e con 1", r.threads);
+        std::process::exit(1);
     }
+    // a run too short for the clock has no speedup
+    let base = reports[0].elapsed;
+    let rows = reports.iter().map(|r| {
+        let speedup = (!base.is_zero() && !r.elapsed.is_zero()).then(|| base.as_secs_f64() / r.elapsed.as_secs_f64());
+        (r, speedup, speedup.map(|s| s / r.threads as f64))
+    });
 
-    #[test]
-    fn test_operation_10() {
-        let result = compute_operation((&vec![&7,&2,&1,&2,&2], &vec![&'-',&'-',&'x',&'+']));
-        assert_eq!(result.unwrap(), (10, "7 - 2 - 1 x 2 + 2".to_string()));
+    match args.puzzle.format {
+        Format::Text => {
+            println!("Numeri: {:?}\nSoluzioni: {}\nEsplorati: {}\n", puzzle.numbers(), reports[0].solutions.len(), reports[0].explored);
+            println!("{:>7} {:>12} {:>8} {:>10}", "thread", "tempo (ms)", "speedup", "efficienza");
+            for (r, speedup, efficiency) in rows {
+                let speedup = speedup.map_or("-".to_string(), |s| format!("{:.2}", s));
+                let efficiency = efficiency.map_or("-".to_string(), |e| format!("{:.0}%", e * 100.0));
+                println!("{:>7} {:>12.3} {:>8} {:>10}", r.threads, millis(r.elapsed), speedup, efficiency);
+            }
+        }
+        Format::Json => {
+            let rows: Vec<_> = rows
+                .map(|(r, speedup, efficiency)| {
+                    json!({ "threads": r.threads, "elapsed_ms": millis(r.elapsed), "speedup": speedup, "efficiency": efficiency })
+                })
+                .collect();
+            let out = json!({
+                "numbers": puzzle.numbers(),
+                "solutions": reports[0].solutions.len(),
+                "explored": reports[0].explored,
+                "runs": args.runs.max(1),
+                "results": rows,
+            });
+            println!("{}", serde_json::to_string_pretty(&out).unwrap());
+        }
+        Format::Csv => {
+            println!("threads,elapsed_ms,speedup,efficiency");
+            // empty fields for the missing speedups
+            let field = |x: Option<f64>| x.map_or(String::new(), |x| format!("{:.3}", x));
+            for (r, speedup, efficiency) in rows {
+                println!("{},{:.3},{},{}", r.threads, millis(r.elapsed), field(speedup), field(efficiency));
+            }
+        }
     }
+}
 
+fn main() {
+    let cli = Cli::parse();
+    match &cli.command {
+        Some(Command::Bench(args)) => bench(args),
+        None => solve(&cli.solve),
+    }
 }
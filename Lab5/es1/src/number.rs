@@ -0,0 +1,184 @@
+// The numbers the expressions are evaluated in. With i64 a division must be exact, as in
+// the original puzzle; with Rational every division by a non zero number is allowed, so
+// 8 / 3 x 3 is 8. Both are checked: an overflow makes the candidate invalid instead of
+// wrapping around or panicking.
+
+use std::cmp::Ordering;
+use std::fmt;
+
+pub trait Number: Copy + PartialEq + From<i64> + fmt::Display {
+    fn try_add(self, other: Self) -> Option<Self>;
+    fn try_sub(self, other: Self) -> Option<Self>;
+    fn try_mul(self, other: Self) -> Option<Self>;
+    fn try_div(self, other: Self) -> Option<Self>;
+}
+
+impl Number for i64 {
+    fn try_add(self, other: i64) -> Option<i64> {
+        self.checked_add(other)
+    }
+
+    fn try_sub(self, other: i64) -> Option<i64> {
+        self.checked_sub(other)
+    }
+
+    fn try_mul(self, other: i64) -> Option<i64> {
+        self.checked_mul(other)
+    }
+
+    // None for a division with a remainder as well
+    fn try_div(self, other: i64) -> Option<i64> {
+        if other == 0 || self.checked_rem(other)? != 0 {
+            None
+        } else {
+            self.checked_div(other)
+        }
+    }
+}
+
+// A fraction in lowest terms with a positive denominator, so that equal values have
+// equal fields. The operations are computed in i128, where the products of two i64
+// can't overflow, and reduced before going back to i64.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct Rational {
+    num: i64,
+    den: i64,
+}
+
+impl Rational {
+    // None if den is 0 or the reduced fraction doesn't fit in i64
+    pub fn new(num: i64, den: i64) -> Option<Rational> {
+        Rational::reduce(num.into(), den.into())
+    }
+
+    pub fn numer(self) -> i64 {
+        self.num
+    }
+
+    pub fn denom(self) -> i64 {
+        self.den
+    }
+
+    pub fn is_integer(self) -> bool {
+        self.den == 1
+    }
+
+    fn reduce(num: i128, den: i128) -> Option<Rational> {
+        if den == 0 {
+            return None;
+        }
+        let g = gcd(num, den);
+        let sign = if den < 0 { -1 } else { 1 };
+        let num = i64::try_from(sign * num / g).ok()?;
+        let den = i64::try_from(sign * den / g).ok()?;
+        Some(Rational { num, den })
+    }
+}
+
+fn gcd(mut a: i128, mut b: i128) -> i128 {
+    while b != 0 {
+        (a, b) = (b, a % b);
+    }
+    a.abs()
+}
+
+impl From<i64> for Rational {
+    fn from(n: i64) -> Rational {
+        Rational { num: n, den: 1 }
+    }
+}
+
+impl Number for Rational {
+    fn try_add(self, other: Rational) -> Option<Rational> {
+        let (a, b, c, d) = (self.num as i128, self.den as i128, other.num as i128, other.den as i128);
+        Rational::reduce(a * d + c * b, b * d)
+    }
+
+    fn try_sub(self, other: Rational) -> Option<Rational> {
+        self.try_add(Rational { num: other.num.checked_neg()?, den: other.den })
+    }
+
+    fn try_mul(self, other: Rational) -> Option<Rational> {
+        Rational::reduce(self.num as i128 * other.num as i128, self.den as i128 * other.den as i128)
+    }
+
+    // None only for a division by 0 (or an overflow)
+    fn try_div(self, other: Rational) -> Option<Rational> {
+        Rational::reduce(self.num as i128 * other.den as i128, self.den as i128 * other.num as i128)
+    }
+}
+
+impl PartialOrd for Rational {
+    fn partial_cmp(&self, other: &Rational) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Rational {
+    // the denominators are positive: a/b < c/d iff a x d < c x b
+    fn cmp(&self, other: &Rational) -> Ordering {
+        (self.num as i128 * other.den as i128).cmp(&(other.num as i128 * self.den as i128))
+    }
+}
+
+impl fmt::Display for Rational {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.den == 1 {
+            write!(f, "{}", self.num)
+        } else {
+            write!(f, "{}/{}", self.num, self.den)
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn r(num: i64, den: i64) -> Rational {
+        Rational::new(num, den).unwrap()
+    }
+
+    #[test]
+    fn lowest_terms() {
+        assert_eq!(r(6, -4), r(-3, 2));
+        assert_eq!((r(6, -4).numer(), r(6, -4).denom()), (-3, 2));
+        assert_eq!(r(0, -5), Rational::from(0));
+        assert_eq!(Rational::new(1, 0), None);
+        assert_eq!(r(-8, 3).to_string(), "-8/3");
+        assert_eq!(r(8, 4).to_string(), "2");
+        assert!(r(8, 4).is_integer() && !r(8, 3).is_integer());
+    }
+
+    #[test]
+    fn arithmetic() {
+        let third = r(1, 3);
+        assert_eq!(third.try_add(r(1, 6)), Some(r(1, 2)));
+        assert_eq!(third.try_sub(r(1, 2)), Some(r(-1, 6)));
+        assert_eq!(r(8, 3).try_mul(3.into()), Some(8.into()));
+        assert_eq!(Rational::from(8).try_div(3.into()), Some(r(8, 3)));
+        assert_eq!(third.try_div(0.into()), None);
+        assert!(r(1, 3) < r(1, 2) && r(-1, 2) < r(-1, 3));
+    }
+
+    #[test]
+    fn overflow_is_none() {
+        let big = Rational::from(i64::MAX);
+        assert_eq!(big.try_add(1.into()), None);
+        assert_eq!(big.try_mul(2.into()), None);
+        assert_eq!(Rational::from(i64::MIN).try_sub(1.into()), None);
+        assert_eq!(Rational::from(1).try_sub(i64::MIN.into()), None);
+        assert_eq!(r(1, i64::MAX).try_div(i64::MAX.into()), None);
+        // the intermediate products overflow i64, the reduced result doesn't
+        assert_eq!(big.try_mul(r(1, i64::MAX)), Some(1.into()));
+    }
+
+    #[test]
+    fn integers() {
+        assert_eq!(8i64.try_div(3), None);
+        assert_eq!(9i64.try_div(3), Some(3));
+        assert_eq!(1i64.try_div(0), None);
+        assert_eq!(i64::MIN.try_div(-1), None);
+        assert_eq!(i64::MAX.try_add(1), None);
+    }
+}
@@ -0,0 +1,135 @@
+// Work stealing over a range of indices: each worker starts with an equal share of the
+// range and takes small chunks from the front of its own share; a worker that runs out
+// steals the back half of the largest share left. Nothing is materialized: a chunk is
+// just a Range, and the work items are computed from the indices by the caller.
+
+use std::ops::Range;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Mutex;
+use std::thread;
+
+pub struct WorkQueue {
+    shares: Vec<Mutex<Range<usize>>>,
+}
+
+impl WorkQueue {
+    pub fn new(total: usize, workers: usize) -> WorkQueue {
+        let workers = workers.max(1);
+        let shares = (0..workers).map(|w| Mutex::new(total * w / workers..total * (w + 1) / workers)).collect();
+        WorkQueue { shares }
+    }
+
+    pub fn workers(&self) -> usize {
+        self.shares.len()
+    }
+
+    // the next chunk of at most `chunk` indices for worker `me`, None when all the
+    // shares are empty
+    pub fn next(&self, me: usize, chunk: usize) -> Option<Range<usize>> {
+        loop {
+            {
+                let mut own = self.shares[me].lock().unwrap();
+                if !own.is_empty() {
+                    let end = own.end.min(own.start + chunk.max(1));
+                    let taken = own.start..end;
+                    own.start = end;
+                    return Some(taken);
+                }
+            }
+            let stolen = self.steal(me)?;
+            *self.shares[me].lock().unwrap() = stolen;
+        }
+    }
+
+    fn steal(&self, me: usize) -> Option<Range<usize>> {
+        loop {
+            let victim = (0..self.shares.len())
+                .filter(|&w| w != me)
+                .map(|w| (self.shares[w].lock().unwrap().len(), w))
+                .max()
+                .filter(|&(len, _)| len > 0)?
+                .1;
+            let mut share = self.shares[victim].lock().unwrap();
+            // the victim may have finished its share in the meantime: look again
+            if share.is_empty() {
+                continue;
+            }
+            let half = share.len().div_ceil(2);
+            let stolen = share.end - half..share.end;
+            share.end -= half;
+            return Some(stolen);
+        }
+    }
+}
+
+// Run f(worker, chunk) on `threads` scoped threads until the range is done or stop is
+// set; tick runs on the calling thread every `interval` while the workers are busy.
+pub fn run<F, T>(total: usize, threads: usize, chunk: usize, stop: &AtomicBool, f: F, mut tick: T, interval: std::time::Duration)
+where
+    F: Fn(usize, Range<usize>) + Sync,
+    T: FnMut(),
+{
+    let queue = WorkQueue::new(total, threads);
+    let running = std::sync::atomic::AtomicUsize::new(queue.workers());
+    let caller = thread::current();
+    thread::scope(|s| {
+        for me in 0..queue.workers() {
+            let (queue, running, caller, f) = (&queue, &running, caller.clone(), &f);
+            s.spawn(move || {
+                while !stop.load(Ordering::Relaxed) {
+                    match queue.next(me, chunk) {
+                        Some(range) => f(me, range),
+                        None => break,
+                    }
+                }
+                running.fetch_sub(1, Ordering::AcqRel);
+                caller.unpark();
+            });
+        }
+        while running.load(Ordering::Acquire) > 0 {
+            thread::park_timeout(interval);
+            tick();
+        }
+    });
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::atomic::AtomicUsize;
+    use std::time::Duration;
+
+    #[test]
+    fn every_index_once() {
+        for (total, threads, chunk) in [(0, 3, 1), (1, 4, 1), (1000, 1, 7), (1000, 7, 3), (97, 16, 100)] {
+            let seen: Vec<AtomicUsize> = (0..total).map(|_| AtomicUsize::new(0)).collect();
+            let stop = AtomicBool::new(false);
+            run(total, threads, chunk, &stop, |_, r| r.for_each(|i| { seen[i].fetch_add(1, Ordering::Relaxed); }), || {}, Duration::from_millis(1));
+            assert!(seen.iter().all(|c| c.load(Ordering::Relaxed) == 1), "{total} {threads} {chunk}");
+        }
+    }
+
+    #[test]
+    fn idle_workers_steal() {
+        // worker 0 gets a share that takes long, the others must help it
+        let queue = WorkQueue::new(100, 4);
+        while queue.next(1, 100).is_some() {}
+        assert_eq!(queue.shares[1].lock().unwrap().len(), 0);
+        // worker 1 took its own 25, then stole half of each of the others, and so on
+        let stolen_from_0 = 25 - queue.shares[0].lock().unwrap().len();
+        assert_eq!(stolen_from_0, 25);
+        assert_eq!(queue.next(0, 1), None);
+    }
+
+    #[test]
+    fn stop_ends_the_workers() {
+        let stop = AtomicBool::new(false);
+        let done = AtomicUsize::new(0);
+        run(1_000_000, 4, 1, &stop, |_, _| {
+            if done.fetch_add(1, Ordering::Relaxed) == 10 {
+                stop.store(true, Ordering::Relaxed);
+            }
+        }, || {}, Duration::from_millis(1));
+        assert!(done.load(Ordering::Relaxed) < 100);
+    }
+}
@@ -0,0 +1,693 @@
+// The puzzle: combine all the numbers, in any order, with the allowed operators so that
+// the expression equals the target.
+//
+// Every candidate is a permutation of the numbers, an operator for each of the gaps
+// between them (any of the allowed ones, so |ops|^(n-1) choices) and, depending on the
+// mode, the way the expression is evaluated:
+// - LeftToRight: one operator after the other, "7 - 2 - 1 x 2 + 2" is ((((7-2)-1)x2)+2);
+// - Precedence: x and / before + and -;
+// - Trees: every way of putting the parentheses, i.e. every binary tree with the
+//   numbers as leaves (Catalan(n-1) of them).
+// The values are computed with integers, where a division must be exact, or with exact
+// fractions (Arithmetic::Rational), where 8 / 3 x 3 is 8; the solutions are the same
+// expressions, only their intermediate results differ.
+//
+// The candidates are generated one at a time, never stored; the solutions are kept in
+// canonical form, so that the same expression found through different permutations or
+// parenthesizations is reported once.
+//
+// The search runs on several threads: the unit of work is a permutation, identified by its
+// index in 0..n! (decoded with the factorial number system), and the index range is split
+// among the threads by the work stealing queue of pool.rs. A permutation that repeats an
+// earlier one (equal numbers swapped) is skipped.
+
+use std::collections::BTreeSet;
+use std::fmt;
+use std::ops::Range;
+use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::Mutex;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use crate::expr::{Expr, Op};
+use crate::number::{Number, Rational};
+use crate::pool;
+
+const PROGRESS_INTERVAL: Duration = Duration::from_millis(200);
+
+// the most numbers a search takes: the permutations of 21 don't fit in a usize
+pub const MAX_NUMBERS: usize = 20;
+// the most in Trees mode, whose shapes are all built before the search: 58786 for 12
+// numbers, but 1.7 billion for 20
+pub const MAX_TREE_NUMBERS: usize = 12;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Mode {
+    #[default]
+    LeftToRight,
+    Precedence,
+    Trees,
+}
+
+impl Mode {
+    // the most numbers a search in this mode takes
+    pub fn max_numbers(self) -> usize {
+        match self {
+            Mode::Trees => MAX_TREE_NUMBERS,
+            _ => MAX_NUMBERS,
+        }
+    }
+}
+
+impl FromStr for Mode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Mode, String> {
+        match s {
+            "left" => Ok(Mode::LeftToRight),
+            "precedence" => Ok(Mode::Precedence),
+            "trees" => Ok(Mode::Trees),
+            _ => Err(format!("modalità sconosciuta {:?}: left, precedence o trees", s)),
+        }
+    }
+}
+
+impl fmt::Display for Mode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Mode::LeftToRight => "left",
+            Mode::Precedence => "precedence",
+            Mode::Trees => "trees",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Arithmetic {
+    // only exact divisions
+    #[default]
+    Integer,
+    Rational,
+}
+
+impl FromStr for Arithmetic {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Arithmetic, String> {
+        match s {
+            "integer" => Ok(Arithmetic::Integer),
+            "rational" => Ok(Arithmetic::Rational),
+            _ => Err(format!("aritmetica sconosciuta {:?}: integer o rational", s)),
+        }
+    }
+}
+
+impl fmt::Display for Arithmetic {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let s = match self {
+            Arithmetic::Integer => "integer",
+            Arithmetic::Rational => "rational",
+        };
+        write!(f, "{}", s)
+    }
+}
+
+#[derive(Debug, Clone)]
+pub struct Puzzle {
+    numbers: Vec<i64>,
+    target: i64,
+    ops: Vec<Op>,
+    mode: Mode,
+    arithmetic: Arithmetic,
+}
+
+impl Puzzle {
+    // all the operators, evaluated left to right with integers
+    pub fn new(numbers: Vec<i64>, target: i64) -> Puzzle {
+        Puzzle { numbers, target, ops: Op::ALL.to_vec(), mode: Mode::LeftToRight, arithmetic: Arithmetic::Integer }
+    }
+
+    // an operator given more than once counts once
+    pub fn ops(mut self, ops: Vec<Op>) -> Puzzle {
+        self.ops.clear();
+        for op in ops {
+            if !self.ops.contains(&op) {
+                self.ops.push(op);
+            }
+        }
+        self
+    }
+
+    pub fn mode(mut self, mode: Mode) -> Puzzle {
+        self.mode = mode;
+        self
+    }
+
+    pub fn arithmetic(mut self, arithmetic: Arithmetic) -> Puzzle {
+        self.arithmetic = arithmetic;
+        self
+    }
+
+    pub fn numbers(&self) -> &[i64] {
+        &self.numbers
+    }
+
+    pub fn target(&self) -> i64 {
+        self.target
+    }
+
+    // the solutions in canonical form, sorted, using all the available threads
+    pub fn solve(&self) -> Vec<String> {
+        self.search().run()
+    }
+
+    pub fn search(&self) -> Search<'_> {
+        let threads = thread::available_parallelism().map_or(1, |n| n.get());
+        Search { puzzle: self, threads, first: false, progress: None }
+    }
+
+    // the solutions among the candidates of the permutations in perms, added to out; stops
+    // when stop is set, and sets it at the first solution if first. Returns the number of
+    // candidates evaluated
+    fn solve_perms<N: Number>(&self, perms: Range<usize>, shapes: &[Shape], first: bool, stop: &AtomicBool, out: &mut BTreeSet<String>) -> usize {
+        let n = self.numbers.len();
+        let gaps = n.saturating_sub(1);
+        let combos = ops_combinations(self.ops.len(), gaps);
+        let target = N::from(self.target);
+        let (mut order, mut nums, mut ops) = (vec![0; n], vec![0; n], vec![Op::Add; gaps]);
+        // the numbers of nums, converted once for all the candidates of the permutation
+        let mut values = vec![N::from(0); n];
+        let (fact, mut unused) = (factorials(n), Vec::with_capacity(n));
+        let mut explored = 0;
+        let mut index = perms.start;
+        while index < perms.end {
+            nth_permutation(index, &mut order, &mut unused, &fact);
+            if let Some(p) = self.repeated_at(&order) {
+                // the orders starting with order[..=p] are the next block of (n-1-p)!
+                // indices, and they all repeat an earlier one
+                let block = fact[n - 1 - p];
+                index = (index / block + 1) * block;
+                continue;
+            }
+            index += 1;
+            for ((slot, value), &i) in nums.iter_mut().zip(values.iter_mut()).zip(&order) {
+                *slot = self.numbers[i];
+                *value = N::from(self.numbers[i]);
+            }
+            for combo in 0..combos {
+                if stop.load(Ordering::Relaxed) {
+                    return explored;
+                }
+                // combo in base |ops|, one digit per gap
+                let mut c = combo;
+                for op in ops.iter_mut() {
+                    *op = self.ops[c % self.ops.len()];
+                    c /= self.ops.len();
+                }
+                for shape in shapes {
+                    explored += 1;
+                    if let Some(e) = self.check(&nums, &values, target, &ops, shape) {
+                        out.insert(e.canonical());
+                        if first {
+                            stop.store(true, Ordering::Relaxed);
+                            return explored;
+                        }
+                    }
+                }
+            }
+        }
+        explored
+    }
+
+    // swapping two equal numbers gives the same candidates: of all the orders of the
+    // indices of equal numbers only the increasing one is searched. The first position
+    // where order breaks this, if any: then so does every order with the same prefix
+    fn repeated_at(&self, order: &[usize]) -> Option<usize> {
+        (0..order.len()).find(|&a| {
+            let i = order[a];
+            order[a + 1..].iter().any(|&j| j < i && self.numbers[i] == self.numbers[j])
+        })
+    }
+
+    // the expression of the candidate, if it equals the target; values are the numbers
+    // nums in the arithmetic of the search
+    fn check<N: Number>(&self, nums: &[i64], values: &[N], target: N, ops: &[Op], shape: &Shape) -> Option<Expr> {
+        let value = match self.mode {
+            Mode::LeftToRight => eval_left(values, ops),
+            Mode::Precedence => eval_precedence(values, ops),
+            Mode::Trees => shape.eval(values, ops),
+        };
+        if value? != target {
+            return None;
+        }
+        Some(match self.mode {
+            Mode::LeftToRight => expr_left(nums, ops),
+            Mode::Precedence => expr_precedence(nums, ops),
+            Mode::Trees => shape.expr(nums, ops),
+        })
+    }
+
+    // only Trees has more than one way to evaluate the same numbers and operators
+    fn shapes(&self) -> Vec<Shape> {
+        match self.mode {
+            Mode::Trees if !self.numbers.is_empty() => Shape::all(self.numbers.len()),
+            _ => vec![Shape::Leaf],
+        }
+    }
+}
+
+// Progress of a search, reported while the threads work
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Progress {
+    // permutations searched, out of total = n! (at least 1)
+    pub done: usize,
+    pub total: usize,
+    // candidates evaluated
+    pub explored: usize,
+    pub found: usize,
+}
+
+// The outcome of a search: the solutions in canonical form, sorted, how long it took and
+// how many candidates were evaluated, by how many threads
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct SolveReport {
+    pub solutions: Vec<String>,
+    pub elapsed: Duration,
+    pub explored: usize,
+    pub threads: usize,
+}
+
+// A search of the solutions of a puzzle, built by Puzzle::search
+pub struct Search<'p> {
+    puzzle: &'p Puzzle,
+    threads: usize,
+    first: bool,
+    progress: Option<Box<dyn FnMut(Progress) + 'p>>,
+}
+
+impl<'p> Search<'p> {
+    // the number of threads, all the available ones by default
+    pub fn threads(mut self, threads: usize) -> Self {
+        self.threads = threads.max(1);
+        self
+    }
+
+    // stop at the first solution found, by whichever thread
+    pub fn first(mut self, first: bool) -> Self {
+        self.first = first;
+        self
+    }
+
+    // f is called on the calling thread every 200 ms while the search runs, and once at
+    // the end
+    pub fn progress<F: FnMut(Progress) + 'p>(mut self, f: F) -> Self {
+        self.progress = Some(Box::new(f));
+        self
+    }
+
+    // the solutions in canonical form, sorted; with first(true) at most one.
+    // panics with more than mode.max_numbers() numbers
+    pub fn run(self) -> Vec<String> {
+        self.report().solutions
+    }
+
+    // the solutions, as run, with the numbers of the search
+    pub fn report(mut self) -> SolveReport {
+        let start = Instant::now();
+        let puzzle = self.puzzle;
+        assert!(puzzle.numbers.len() <= puzzle.mode.max_numbers(), "too many numbers to search in {} mode", puzzle.mode);
+        let total = (1..=puzzle.numbers.len()).try_fold(1usize, |f, k| f.checked_mul(k)).expect("too many numbers to search");
+        if puzzle.ops.is_empty() && puzzle.numbers.len() > 1 {
+            return SolveReport { solutions: Vec::new(), elapsed: start.elapsed(), explored: 0, threads: self.threads };
+        }
+        let shapes = puzzle.shapes();
+        let found = Mutex::new(BTreeSet::new());
+        let (done, explored, found_count) = (AtomicUsize::new(0), AtomicUsize::new(0), AtomicUsize::new(0));
+        let stop = AtomicBool::new(false);
+        let work = |_, perms: Range<usize>| {
+            let len = perms.len();
+            let mut out = BTreeSet::new();
+            let n = match puzzle.arithmetic {
+                Arithmetic::Integer => puzzle.solve_perms::<i64>(perms, &shapes, self.first, &stop, &mut out),
+                Arithmetic::Rational => puzzle.solve_perms::<Rational>(perms, &shapes, self.first, &stop, &mut out),
+            };
+            explored.fetch_add(n, Ordering::Relaxed);
+            // solutions are rare, the lock is taken only when there are some
+            if !out.is_empty() {
+                let mut found = found.lock().unwrap();
+                found.append(&mut out);
+                found_count.store(found.len(), Ordering::Relaxed);
+            }
+            done.fetch_add(len, Ordering::Relaxed);
+        };
+        let mut tick = || {
+            let p = Progress {
+                done: done.load(Ordering::Relaxed),
+                total,
+                explored: explored.load(Ordering::Relaxed),
+                found: found_count.load(Ordering::Relaxed),
+            };
+            if let Some(f) = &mut self.progress {
+                f(p);
+            }
+        };
+        pool::run(total, self.threads, 1, &stop, work, &mut tick, PROGRESS_INTERVAL);
+        tick();
+
+        let found = found.into_inner().unwrap().into_iter();
+        // more than one thread may have found a solution before stopping
+        let solutions = if self.first { found.take(1).collect() } else { found.collect() };
+        SolveReport { solutions, elapsed: start.elapsed(), explored: explored.into_inner(), threads: self.threads }
+    }
+}
+
+// 0!, 1!, ..., n!
+fn factorials(n: usize) -> Vec<usize> {
+    (0..=n).scan(1usize, |f, k| {
+        *f *= k.max(1);
+        Some(*f)
+    }).collect()
+}
+
+// the k-th permutation of 0..order.len() in lexicographic order, written into order:
+// the digits of k in the factorial number system pick the next element among the unused.
+// unused is scratch space, fact the factorials up to order.len()
+fn nth_permutation(mut k: usize, order: &mut [usize], unused: &mut Vec<usize>, fact: &[usize]) {
+    let n = order.len();
+    unused.clear();
+    unused.extend(0..n);
+    for (i, slot) in order.iter_mut().enumerate() {
+        let radix = fact[n - 1 - i];
+        *slot = unused.remove(k / radix);
+        k %= radix;
+    }
+}
+
+// |ops|^gaps, the choices of an operator for every gap
+fn ops_combinations(ops: usize, gaps: usize) -> usize {
+    u32::try_from(gaps).ok().and_then(|g| ops.checked_pow(g)).expect("too many operator combinations to search")
+}
+
+fn eval_left<N: Number>(nums: &[N], ops: &[Op]) -> Option<N> {
+    let (first, rest) = nums.split_first()?;
+    ops.iter().zip(rest).try_fold(*first, |acc, (op, &n)| op.apply(acc, n))
+}
+
+fn expr_left(nums: &[i64], ops: &[Op]) -> Expr {
+    let first = Expr::Num(nums[0]);
+    ops.iter().zip(&nums[1..]).fold(first, |acc, (&op, &n)| Expr::bin(op, acc, Expr::Num(n)))
+}
+
+// the products are computed as soon as they're complete, and added to the total
+fn eval_precedence<N: Number>(nums: &[N], ops: &[Op]) -> Option<N> {
+    let (first, rest) = nums.split_first()?;
+    let (mut total, mut sign, mut term) = (N::from(0), Op::Add, *first);
+    for (&op, &n) in ops.iter().zip(rest) {
+        match op {
+            Op::Mul | Op::Div => term = op.apply(term, n)?,
+            Op::Add | Op::Sub => {
+                total = sign.apply(total, term)?;
+                (sign, term) = (op, n);
+            }
+        }
+    }
+    sign.apply(total, term)
+}
+
+fn expr_precedence(nums: &[i64], ops: &[Op]) -> Expr {
+    let mut total: Option<(Expr, Op)> = None;
+    let mut term = Expr::Num(nums[0]);
+    for (&op, &n) in ops.iter().zip(&nums[1..]) {
+        match op {
+            Op::Mul | Op::Div => term = Expr::bin(op, term, Expr::Num(n)),
+            Op::Add | Op::Sub => {
+                let done = match total.take() {
+                    Some((t, sign)) => Expr::bin(sign, t, term),
+                    None => term,
+                };
+                total = Some((done, op));
+                term = Expr::Num(n);
+            }
+        }
+    }
+    match total {
+        Some((t, sign)) => Expr::bin(sign, t, term),
+        None => term,
+    }
+}
+
+// The shape of an expression tree: the numbers go to the leaves and the operators to the
+// inner nodes, both from left to right.
+#[derive(Debug, Clone)]
+enum Shape {
+    Leaf,
+    Node(Box<Shape>, Box<Shape>),
+}
+
+impl Shape {
+    // all the shapes with n > 0 leaves, built from the smaller ones, each built once
+    fn all(n: usize) -> Vec<Shape> {
+        // by_leaves[k]: the shapes with k leaves
+        let mut by_leaves = vec![Vec::new(), vec![Shape::Leaf]];
+        for k in 2..=n {
+            let mut out = Vec::new();
+            for left in 1..k {
+                for l in &by_leaves[left] {
+                    for r in &by_leaves[k - left] {
+                        out.push(Shape::Node(Box::new(l.clone()), Box::new(r.clone())));
+                    }
+                }
+            }
+            by_leaves.push(out);
+        }
+        by_leaves.swap_remove(n)
+    }
+
+    fn eval<N: Number>(&self, nums: &[N], ops: &[Op]) -> Option<N> {
+        self.eval_from(&mut nums.iter(), &mut ops.iter())
+    }
+
+    fn eval_from<'a, N: Number + 'a>(&self, nums: &mut impl Iterator<Item = &'a N>, ops: &mut impl Iterator<Item = &'a Op>) -> Option<N> {
+        match self {
+            Shape::Leaf => nums.next().copied(),
+            Shape::Node(l, r) => {
+                let a = l.eval_from(nums, ops)?;
+                let op = *ops.next()?;
+                let b = r.eval_from(nums, ops)?;
+                op.apply(a, b)
+            }
+        }
+    }
+
+    fn expr(&self, nums: &[i64], ops: &[Op]) -> Expr {
+        self.expr_from(&mut nums.iter(), &mut ops.iter())
+    }
+
+    fn expr_from<'a>(&self, nums: &mut impl Iterator<Item = &'a i64>, ops: &mut impl Iterator<Item = &'a Op>) -> Expr {
+        match self {
+            Shape::Leaf => Expr::Num(*nums.next().expect("a number for each leaf")),
+            Shape::Node(l, r) => {
+                let a = l.expr_from(nums, ops);
+                let op = *ops.next().expect("an operator for each node");
+                let b = r.expr_from(nums, ops);
+                Expr::bin(op, a, b)
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn eval(mode: Mode, nums: &[i64], ops: &str) -> Option<i64> {
+        let ops: Vec<Op> = ops.chars().filter_map(Op::from_symbol).collect();
+        match mode {
+            Mode::LeftToRight => eval_left(nums, &ops),
+            Mode::Precedence => eval_precedence(nums, &ops),
+            Mode::Trees => unreachable!(),
+        }
+    }
+
+    #[test]
+    fn test_division_zero() {
+        assert_eq!(eval(Mode::LeftToRight, &[2, 0, 2, 2, 1], "/-x+"), None);
+    }
+
+    #[test]
+    fn test_not_integer_division() {
+        assert_eq!(eval(Mode::LeftToRight, &[2, 3, 2, 2, 1], "/-x+"), None);
+    }
+
+    #[test]
+    fn test_operation_negative() {
+        assert_eq!(eval(Mode::LeftToRight, &[2, 7, 2, 2, 1], "--x+"), Some(-13));
+        let e = expr_left(&[2, 7, 2, 2, 1], &[Op::Sub, Op::Sub, Op::Mul, Op::Add]);
+        assert_eq!(e.to_string(), "(2 - 7 - 2) x 2 + 1");
+    }
+
+    #[test]
+    fn test_operation_10() {
+        assert_eq!(eval(Mode::LeftToRight, &[7, 2, 1, 2, 2], "--x+"), Some(10));
+    }
+
+    #[test]
+    fn precedence() {
+        assert_eq!(eval(Mode::Precedence, &[7, 2, 1, 2, 2], "--x+"), Some(5));
+        assert_eq!(eval(Mode::Precedence, &[8, 4, 2, 3], "/x-"), Some(1));
+        assert_eq!(eval(Mode::Precedence, &[8, 3, 2], "-/"), None);
+        let e = expr_precedence(&[7, 2, 1, 2, 2], &[Op::Sub, Op::Sub, Op::Mul, Op::Add]);
+        assert_eq!((e.to_string(), e.eval()), ("7 - 2 - 1 x 2 + 2".to_string(), Some(5)));
+    }
+
+    #[test]
+    fn catalan_shapes() {
+        let counts: Vec<usize> = (1..=6).map(|n| Shape::all(n).len()).collect();
+        assert_eq!(counts, [1, 1, 2, 5, 14, 42]);
+        assert_eq!(Shape::all(MAX_TREE_NUMBERS).len(), 58786);
+    }
+
+    #[test]
+    #[should_panic(expected = "too many numbers")]
+    fn trees_take_fewer_numbers() {
+        Puzzle::new(vec![1; MAX_TREE_NUMBERS + 1], 1).mode(Mode::Trees).solve();
+    }
+
+    #[test]
+    fn all_operator_orders_are_tried() {
+        // 1 - 2 + 3 = 2 needs "- +", which combinations_with_replacement never produced
+        let p = Puzzle::new(vec![1, 2, 3], 2).ops(vec![Op::Add, Op::Sub]);
+        assert!(p.solve().contains(&"1 + 3 - 2".to_string()));
+    }
+
+    #[test]
+    fn repeated_operators_count_once() {
+        let p = Puzzle::new(vec![1, 2, 3], 6);
+        let twice = p.clone().ops([Op::ALL, Op::ALL].concat()).search().threads(1).report();
+        let once = p.search().threads(1).report();
+        assert_eq!((twice.solutions, twice.explored), (once.solutions, once.explored));
+    }
+
+    #[test]
+    fn solutions_are_deduplicated() {
+        // 2 + 2 + 2 + ... in any order is a single solution
+        let p = Puzzle::new(vec![2, 2, 2], 6).ops(vec![Op::Add]);
+        assert_eq!(p.solve(), ["2 + 2 + 2"]);
+        let p = Puzzle::new(vec![1, 2, 3], 6).ops(vec![Op::Add, Op::Mul]).mode(Mode::Trees);
+        assert_eq!(p.solve(), ["1 + 2 + 3", "1 x 2 x 3"]);
+    }
+
+    #[test]
+    fn multi_digit_operands_and_trees() {
+        // 24 with 10, 10, 4, 4 needs parentheses: (10 x 10 - 4) / 4
+        let p = Puzzle::new(vec![10, 10, 4, 4], 24);
+        assert!(p.mode(Mode::Precedence).solve().is_empty());
+        let p = Puzzle::new(vec![10, 10, 4, 4], 24).mode(Mode::Trees);
+        assert_eq!(p.solve(), ["(10 x 10 - 4) / 4"]);
+    }
+
+    #[test]
+    fn permutations_by_index() {
+        let (mut order, mut unused, fact) = ([0; 3], Vec::new(), factorials(3));
+        assert_eq!(fact, [1, 1, 2, 6]);
+        let all: Vec<[usize; 3]> = (0..6)
+            .map(|k| {
+                nth_permutation(k, &mut order, &mut unused, &fact);
+                order
+            })
+            .collect();
+        assert_eq!(all, [[0, 1, 2], [0, 2, 1], [1, 0, 2], [1, 2, 0], [2, 0, 1], [2, 1, 0]]);
+    }
+
+    #[test]
+    fn repeated_orders_are_skipped_by_block() {
+        let p = Puzzle::new(vec![1, 2, 1], 4);
+        assert_eq!(p.repeated_at(&[0, 1, 2]), None);
+        assert_eq!(p.repeated_at(&[1, 0, 2]), None);
+        assert_eq!(p.repeated_at(&[1, 2, 0]), Some(1));
+        assert_eq!(p.repeated_at(&[2, 0, 1]), Some(0));
+        // a single order of eight equal numbers is searched, out of 8!
+        let report = Puzzle::new(vec![5; 8], 40).ops(vec![Op::Add, Op::Mul]).search().threads(2).report();
+        assert_eq!((report.solutions.len(), report.explored), (1, 1 << 7));
+    }
+
+    #[test]
+    fn same_solutions_with_any_number_of_threads() {
+        let p = Puzzle::new(vec![7, 2, 1, 2, 2], 10).mode(Mode::Precedence);
+        let one = p.search().threads(1).run();
+        assert!(!one.is_empty());
+        for threads in [2, 3, 8] {
+            assert_eq!(p.search().threads(threads).run(), one);
+        }
+    }
+
+    #[test]
+    fn first_stops_at_a_solution() {
+        let p = Puzzle::new(vec![1, 2, 3, 4, 5, 6], 21);
+        let all = p.search().threads(2).run();
+        let mut last = None;
+        let first = p.search().threads(2).first(true).progress(|pr| last = Some(pr)).run();
+        assert_eq!(first.len(), 1);
+        assert!(all.contains(&first[0]));
+        let last = last.unwrap();
+        assert!(last.done < last.total && last.found >= 1);
+    }
+
+    #[test]
+    fn report_counts_the_candidates() {
+        let p = Puzzle::new(vec![7, 2, 1, 2, 2], 10);
+        let report = p.search().threads(3).report();
+        assert_eq!(report.solutions, p.solve());
+        // 5! / 3! different orders, 4^4 operators
+        assert_eq!((report.explored, report.threads), (20 * 256, 3));
+        let p = Puzzle::new(vec![1, 2], 3).ops(vec![]);
+        assert_eq!(p.search().threads(1).report().explored, 0);
+    }
+
+    #[test]
+    fn progress_reaches_the_total() {
+        let mut last = None;
+        let p = Puzzle::new(vec![10, 10, 4, 4], 24).mode(Mode::Trees);
+        let sols = p.search().threads(3).progress(|pr| last = Some(pr)).run();
+        assert_eq!(sols, ["(10 x 10 - 4) / 4"]);
+        let last = last.unwrap();
+        // 4! orders, 6 of them different, 4^3 operators, 5 trees
+        assert_eq!((last.done, last.total, last.explored, last.found), (24, 24, 6 * 64 * 5, 1));
+    }
+
+    #[test]
+    fn modes_parse_and_print() {
+        for m in [Mode::LeftToRight, Mode::Precedence, Mode::Trees] {
+            assert_eq!(m.to_string().parse::<Mode>(), Ok(m));
+        }
+        assert!("x".parse::<Mode>().is_err());
+        for a in [Arithmetic::Integer, Arithmetic::Rational] {
+            assert_eq!(a.to_string().parse::<Arithmetic>(), Ok(a));
+        }
+        assert!("real".parse::<Arithmetic>().is_err());
+    }
+
+    #[test]
+    fn fractional_intermediate_results() {
+        // 1 / 3 + 1 x 3, left to right: 1 / 3 isn't an integer
+        let p = Puzzle::new(vec![1, 3, 1, 3], 4);
+        assert_eq!(p.solve(), ["3 + 3 - 1 - 1"]);
+        assert_eq!(p.arithmetic(Arithmetic::Rational).solve(), ["3 + 3 - 1 - 1", "3 x (1 + 1 / 3)"]);
+        // the classic 24 with 3, 3, 8, 8
+        let p = Puzzle::new(vec![3, 3, 8, 8], 24).mode(Mode::Trees);
+        assert!(p.solve().is_empty());
+        assert_eq!(p.arithmetic(Arithmetic::Rational).solve(), ["8 / (3 - 8 / 3)"]);
+    }
+
+    #[test]
+    fn overflow_is_not_a_solution() {
+        // 2^62 x 4 wraps around to 0 in i64
+        let big = 1 << 62;
+        for a in [Arithmetic::Integer, Arithmetic::Rational] {
+            let p = Puzzle::new(vec![big, 4], 0).ops(vec![Op::Mul]).arithmetic(a);
+            assert!(p.solve().is_empty());
+        }
+    }
+}
@@ -0,0 +1,52 @@
+// cargo bench --bench barrier
+//
+// CyclicBarrier against std::sync::Barrier: n threads going through the barrier ROUNDS
+// times. The CyclicBarrier carries an item per thread and hands the list back to all of
+// them, so it's measured both with () and with a usize item.
+
+use std::hint::black_box;
+use std::sync::Barrier;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use es3::cb::CyclicBarrier;
+
+const ROUNDS: usize = 10_000;
+
+fn time(threads: usize, f: impl Fn(usize) + Sync) -> Duration {
+    let start = Instant::now();
+    thread::scope(|s| {
+        for t in 0..threads {
+            let f = &f;
+            s.spawn(move || f(t));
+        }
+    });
+    start.elapsed()
+}
+
+fn main() {
+    println!("{} rounds\n", ROUNDS);
+    println!("{:>7} {:>14} {:>14} {:>14} {:>8}", "threads", "std", "cyclic ()", "cyclic usize", "ratio");
+    for threads in [2, 4, 8, 16] {
+        let std_barrier = Barrier::new(threads);
+        let std_time = time(threads, |_| {
+            for _ in 0..ROUNDS {
+                black_box(std_barrier.wait());
+            }
+        });
+        let unit = CyclicBarrier::new(threads);
+        let unit_time = time(threads, |_| {
+            for _ in 0..ROUNDS {
+                black_box(unit.wait(()).unwrap());
+            }
+        });
+        let items = CyclicBarrier::new(threads);
+        let items_time = time(threads, |t| {
+            for _ in 0..ROUNDS {
+                black_box(items.wait(t).unwrap());
+            }
+        });
+        let ratio = unit_time.as_secs_f64() / std_time.as_secs_f64();
+        println!("{:>7} {:>14.2?} {:>14.2?} {:>14.2?} {:>7.2}x", threads, std_time, unit_time, items_time, ratio);
+    }
+}
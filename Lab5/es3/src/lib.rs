@@ -1,61 +1,346 @@
+pub mod phaser;
+
 pub mod cb {
-    use std::{fmt::Debug, sync::{Condvar, Mutex}};
-    #[derive(Debug, Clone)]
-    struct Barrier<T: Debug + Clone> {
-        items: Vec<T>,
-        open: bool,
-        n_threads: usize,
-        limit: usize
+    // A reusable barrier for a fixed number of parties, each bringing an item: when the
+    // last one arrives all of them leave with the items of the round, in arrival order.
+    //
+    // The rounds are numbered by a generation counter, which moves on when a round is
+    // complete or broken. A waiter sleeps until the counter moves past its own round, so
+    // it wakes once, with a single notify_all per round and none when the waiters leave.
+    // Then it takes the outcome of its round from `done`, where the outcome stays until
+    // all the waiters of the round have read it: the next round may already be filling
+    // up, or even be over, when a slow waiter gets to run.
+    //
+    // The barrier breaks when a waiter times out, when a thread holding a guard() panics,
+    // when the barrier action panics and on reset(): the waiters of the round get
+    // BarrierBroken, and so does every later wait until reset() is called.
+
+    use std::collections::VecDeque;
+    use std::fmt;
+    use std::panic::{self, AssertUnwindSafe};
+    use std::thread;
+    use std::time::{Duration, Instant};
+
+    #[cfg(loom)]
+    use loom::sync::{Condvar, Mutex};
+    #[cfg(not(loom))]
+    use std::sync::{Condvar, Mutex};
+
+    type Action<T> = Box<dyn FnMut(&[T]) + Send>;
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BarrierBroken {
+        // this waiter timed out, and broke the barrier for the others
+        TimedOut,
+        // the barrier was broken by someone else, or reset
+        Broken,
     }
 
-    impl<T: Debug + Clone> Barrier<T>{
-        fn new(limit: usize) -> Self {
-            Self {items: vec![], open: false, n_threads: 0, limit}
+    impl fmt::Display for BarrierBroken {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                BarrierBroken::TimedOut => write!(f, "barrier wait timed out"),
+                BarrierBroken::Broken => write!(f, "barrier broken"),
+            }
         }
     }
 
-    pub struct CyclicBarrier<T: Debug + Clone>{
-        cb: (Mutex<Barrier<T>>,Condvar),
+    impl std::error::Error for BarrierBroken {}
+
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    pub struct WaitResult<T> {
+        // the items of all the parties, in arrival order
+        pub items: Vec<T>,
+        // true for the last to arrive, which ran the barrier action
+        pub is_leader: bool,
+    }
+
+    // the outcome of a finished round, for the waiters that haven't read it yet
+    struct Round<T> {
+        generation: u64,
+        result: Result<Vec<T>, BarrierBroken>,
+        readers: usize,
+    }
 
+    struct State<T> {
+        generation: u64,
+        // the items of the current round
+        items: Vec<T>,
+        broken: bool,
+        done: VecDeque<Round<T>>,
+        action: Option<Action<T>>,
     }
 
-    impl<T: Debug + Clone> CyclicBarrier<T> {
-        pub fn new(limit: usize) -> Self {
-            CyclicBarrier {
-                cb: (Mutex::new(Barrier::new(limit)),Condvar::new())
+    impl<T: Clone> State<T> {
+        // close the current round with result, for readers waiters still asleep
+        fn finish(&mut self, result: Result<Vec<T>, BarrierBroken>, readers: usize) {
+            if readers > 0 {
+                self.done.push_back(Round { generation: self.generation, result, readers });
             }
+            self.generation += 1;
         }
 
-        pub fn wait(&self, item: T) -> Vec<T>{
-            let (lock, cv) = &self.cb;
+        // the outcome of round generation, which is over; the last reader takes it
+        fn collect(&mut self, generation: u64) -> Result<Vec<T>, BarrierBroken> {
+            let i = self.done.iter().position(|r| r.generation == generation).expect("the outcome of a finished round");
+            self.done[i].readers -= 1;
+            if self.done[i].readers == 0 {
+                self.done.remove(i).unwrap().result
+            } else {
+                self.done[i].result.clone()
+            }
+        }
+    }
+
+    pub struct CyclicBarrier<T> {
+        state: Mutex<State<T>>,
+        cv: Condvar,
+        parties: usize,
+    }
 
-            let mut barrier = lock.lock().unwrap();
-            barrier.items.push(item);
+    impl<T: Clone> CyclicBarrier<T> {
+        // panics if parties is 0
+        pub fn new(parties: usize) -> Self {
+            assert!(parties > 0, "CyclicBarrier: parties must be positive");
+            let state = State { generation: 0, items: Vec::with_capacity(parties), broken: false, done: VecDeque::new(), action: None };
+            CyclicBarrier { state: Mutex::new(state), cv: Condvar::new(), parties }
+        }
 
-            // wait while the barrier is close
-            while !(*barrier).open {
-                // increment threads in the barrier
-                (*barrier).n_threads += 1;
-                if (*barrier).n_threads == (*barrier).limit {
-                    (*barrier).open = true; // now the door is open (the threads can exit and new cannot entry)
-                    cv.notify_all();
-                }else{
-                    // go to sleep except in the case of the last that open the door and exit
-                    barrier = cv.wait(barrier).unwrap();
+        // f runs once per round with the items, on the last thread to arrive and before
+        // the others are released; it must not use the barrier. If it panics the barrier
+        // breaks and the panic goes on in the leader
+        pub fn with_action<F: FnMut(&[T]) + Send + 'static>(self, f: F) -> Self {
+            self.state.lock().unwrap().action = Some(Box::new(f));
+            self
+        }
+
+        pub fn parties(&self) -> usize {
+            self.parties
+        }
+
+        // the parties waiting for the current round
+        pub fn waiting(&self) -> usize {
+            self.state.lock().unwrap().items.len()
+        }
+
+        // the number of rounds finished, completed or broken
+        pub fn generation(&self) -> u64 {
+            self.state.lock().unwrap().generation
+        }
+
+        pub fn is_broken(&self) -> bool {
+            self.state.lock().unwrap().broken
+        }
+
+        pub fn wait(&self, item: T) -> Result<WaitResult<T>, BarrierBroken> {
+            self.wait_until(item, None)
+        }
+
+        // as wait, but if the round isn't complete after timeout the barrier breaks
+        pub fn wait_timeout(&self, item: T, timeout: Duration) -> Result<WaitResult<T>, BarrierBroken> {
+            self.wait_until(item, Some(Instant::now() + timeout))
+        }
+
+        // break the current round, if someone is waiting, and start again with a working
+        // barrier
+        pub fn reset(&self) {
+            let mut state = self.state.lock().unwrap();
+            self.break_round(&mut state, 0);
+            state.broken = false;
+        }
+
+        // a guard that breaks the barrier if the thread panics while holding it, so that
+        // the other parties don't wait forever for a thread that won't arrive
+        pub fn guard(&self) -> PanicGuard<'_, T> {
+            PanicGuard(self)
+        }
+
+        fn wait_until(&self, item: T, deadline: Option<Instant>) -> Result<WaitResult<T>, BarrierBroken> {
+            let mut state = self.state.lock().unwrap();
+            if state.broken {
+                return Err(BarrierBroken::Broken);
+            }
+            state.items.push(item);
+            let generation = state.generation;
+
+            if state.items.len() == self.parties {
+                let items = std::mem::replace(&mut state.items, Vec::with_capacity(self.parties));
+                if let Some(action) = &mut state.action {
+                    if let Err(e) = panic::catch_unwind(AssertUnwindSafe(|| action(&items))) {
+                        state.items = items;
+                        self.break_round(&mut state, 1);
+                        drop(state);
+                        panic::resume_unwind(e);
+                    }
                 }
-                
-                (*barrier).n_threads -= 1;
+                state.finish(Ok(items.clone()), self.parties - 1);
+                self.cv.notify_all();
+                return Ok(WaitResult { items, is_leader: true });
             }
-            let res = barrier.items.clone();
-            // if all the threads are out close the door (the threads can now entry again)
-            if (*barrier).n_threads == 0 {
-                (*barrier).open = false;
-                barrier.items = vec![];
-                cv.notify_all();
+
+            while state.generation == generation {
+                match deadline {
+                    None => state = self.cv.wait(state).unwrap(),
+                    Some(deadline) => {
+                        let left = deadline.saturating_duration_since(Instant::now());
+                        if left.is_zero() {
+                            self.break_round(&mut state, 1);
+                            return Err(BarrierBroken::TimedOut);
+                        }
+                        state = self.cv.wait_timeout(state, left).unwrap().0;
+                    }
+                }
             }
-            res
+            state.collect(generation).map(|items| WaitResult { items, is_leader: false })
         }
 
+        // the waiters of the current round leave with an error, but for the `leaving` ones
+        // that are breaking it, and the barrier stays broken; nothing to do if nobody is
+        // waiting
+        fn break_round(&self, state: &mut State<T>, leaving: usize) {
+            if state.items.is_empty() {
+                return;
+            }
+            let asleep = state.items.len() - leaving;
+            state.items.clear();
+            state.broken = true;
+            state.finish(Err(BarrierBroken::Broken), asleep);
+            self.cv.notify_all();
+        }
+
+        fn break_barrier(&self) {
+            let mut state = self.state.lock().unwrap();
+            self.break_round(&mut state, 0);
+            state.broken = true;
+        }
     }
 
-}
\ No newline at end of file
+    pub struct PanicGuard<'a, T: Clone>(&'a CyclicBarrier<T>);
+
+    impl<T: Clone> Drop for PanicGuard<'_, T> {
+        fn drop(&mut self) {
+            if thread::panicking() {
+                self.0.break_barrier();
+            }
+        }
+    }
+
+    #[cfg(all(test, not(loom)))]
+    mod tests {
+        use super::*;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+        use std::sync::Arc;
+
+        // run f(i) on n threads, returning the results in thread order
+        fn run<R: Send>(n: usize, f: impl Fn(usize) -> R + Sync) -> Vec<R> {
+            thread::scope(|s| {
+                let handles: Vec<_> = (0..n).map(|i| s.spawn({
+                    let f = &f;
+                    move || f(i)
+                })).collect();
+                handles.into_iter().map(|h| h.join().unwrap()).collect()
+            })
+        }
+
+        #[test]
+        fn every_round_collects_all_the_items() {
+            let barrier = CyclicBarrier::new(4);
+            let results = run(4, |i| {
+                (0..50).map(|round| barrier.wait(i * 1000 + round).unwrap()).collect::<Vec<_>>()
+            });
+            for round in 0..50 {
+                let mut items = results[0][round].items.clone();
+                items.sort();
+                assert_eq!(items, (0..4).map(|i| i * 1000 + round).collect::<Vec<_>>());
+                assert!(results.iter().all(|r| r[round].items == results[0][round].items));
+                assert_eq!(results.iter().filter(|r| r[round].is_leader).count(), 1);
+            }
+        }
+
+        #[test]
+        fn action_runs_once_per_round_on_the_leader() {
+            let sums = Arc::new(Mutex::new(Vec::new()));
+            let log = sums.clone();
+            let barrier = CyclicBarrier::new(3).with_action(move |items: &[usize]| log.lock().unwrap().push(items.iter().sum::<usize>()));
+            let leaders = AtomicUsize::new(0);
+            run(3, |i| {
+                for _ in 0..10 {
+                    if barrier.wait(i).unwrap().is_leader {
+                        leaders.fetch_add(1, Ordering::Relaxed);
+                    }
+                }
+            });
+            assert_eq!(*sums.lock().unwrap(), [3; 10]);
+            assert_eq!(leaders.load(Ordering::Relaxed), 10);
+        }
+
+        #[test]
+        fn a_timeout_breaks_the_barrier_for_everyone() {
+            let barrier = CyclicBarrier::new(3);
+            let results = run(2, |i| {
+                if i == 0 {
+                    barrier.wait_timeout(i, Duration::from_millis(50))
+                } else {
+                    barrier.wait(i)
+                }
+            });
+            assert_eq!(results[0], Err(BarrierBroken::TimedOut));
+            assert_eq!(results[1], Err(BarrierBroken::Broken));
+            assert!(barrier.is_broken());
+            assert_eq!(barrier.wait(9), Err(BarrierBroken::Broken));
+            barrier.reset();
+            assert!(!barrier.is_broken());
+            let results = run(3, |i| barrier.wait_timeout(i, Duration::from_secs(10)).map(|r| r.items.len()));
+            assert_eq!(results, [Ok(3); 3]);
+        }
+
+        #[test]
+        fn reset_breaks_the_waiters() {
+            let barrier = CyclicBarrier::new(2);
+            let results = run(2, |i| {
+                if i == 0 {
+                    barrier.wait(i).map(|_| ())
+                } else {
+                    while barrier.waiting() == 0 {
+                        thread::yield_now();
+                    }
+                    barrier.reset();
+                    Ok(())
+                }
+            });
+            assert_eq!(results[0], Err(BarrierBroken::Broken));
+            assert!(!barrier.is_broken());
+            assert_eq!(barrier.waiting(), 0);
+        }
+
+        #[test]
+        fn a_panic_breaks_the_barrier() {
+            let barrier = CyclicBarrier::new(3);
+            let results = run(3, |i| {
+                let result = panic::catch_unwind(AssertUnwindSafe(|| {
+                    let _guard = barrier.guard();
+                    if i == 0 {
+                        panic!("party 0 dies before the barrier");
+                    }
+                    barrier.wait(i).map(|_| ())
+                }));
+                result.unwrap_or(Ok(()))
+            });
+            assert_eq!(results, [Ok(()), Err(BarrierBroken::Broken), Err(BarrierBroken::Broken)]);
+
+            // and so does a panic in the action
+            let barrier = CyclicBarrier::new(2).with_action(|_: &[i32]| panic!("action"));
+            let results = run(2, |i| panic::catch_unwind(AssertUnwindSafe(|| barrier.wait(i as i32).map(|_| ()))));
+            let (panicked, broken): (Vec<_>, Vec<_>) = results.into_iter().partition(|r| r.is_err());
+            assert_eq!((panicked.len(), broken.len()), (1, 1));
+            assert_eq!(broken[0].as_ref().unwrap(), &Err(BarrierBroken::Broken));
+            assert!(barrier.is_broken());
+        }
+
+        #[test]
+        #[should_panic]
+        fn zero_parties() {
+            CyclicBarrier::<()>::new(0);
+        }
+    }
+}
@@ -1,21 +1,46 @@
 use std::sync::Arc;
 use es3::cb;
+use es3::phaser::Phaser;
 
 fn main() {
     
-    let abarrier = Arc::new(cb::CyclicBarrier::new(3));
+    let abarrier = Arc::new(cb::CyclicBarrier::new(3).with_action(|items: &[i32]| println!("round complete: {:?}", items)));
     let mut vt = Vec::new();
     for i in 0..3 {
         let cbarrier = abarrier.clone();
         vt.push(std::thread::spawn(move || {
+            let _guard = cbarrier.guard();
             for j in 0..10 {
-                let res = cbarrier.wait(j);
-                println!("after barrier {} {}, elements: {:?}", i, j, res);
+                let res = cbarrier.wait(j).expect("barrier broken");
+                println!("after barrier {} {}, elements: {:?}{}", i, j, res.items, if res.is_leader { " (leader)" } else { "" });
             }
         }));
     }
     for t in vt {
         t.join().unwrap();
     }
-        
+
+    // stages of a pipeline joining and leaving a phaser: stage i works for i + 1 phases
+    let phaser = Arc::new(Phaser::new(1));
+    let mut stages = Vec::new();
+    for i in 0..3 {
+        let phaser = phaser.clone();
+        phaser.register().unwrap();
+        stages.push(std::thread::spawn(move || {
+            for _ in 0..i {
+                let phase = phaser.arrive_and_await_advance().unwrap();
+                println!("stage {} in phase {}", i, phase);
+            }
+            phaser.arrive_and_deregister().unwrap();
+        }));
+    }
+    while phaser.registered_parties() > 1 {
+        let phase = phaser.arrive_and_await_advance().unwrap();
+        println!("phase {}: {} parties left", phase, phaser.registered_parties());
+    }
+    phaser.arrive_and_deregister().unwrap();
+    println!("terminated: {}", phaser.is_terminated());
+    for t in stages {
+        t.join().unwrap();
+    }
 }
@@ -0,0 +1,496 @@
+// A barrier with a changing number of parties, after java.util.concurrent.Phaser.
+//
+// A phase ends when all the registered parties have arrived; then the phase number goes
+// up by one and whoever waits for it is released. Parties can register and deregister at
+// any time, and arrive() doesn't wait: a stage of a pipeline can signal that it's done
+// with a phase and go on, and only the ones that need to wait call await_advance.
+// At the end of a phase on_advance decides whether the phaser terminates: by default
+// when no parties are left. After termination every operation returns Err(Terminated).
+//
+// Phasers can be tiered: a child phaser is a single party of its parent, which arrives
+// when all the parties of the child have arrived. Each phaser has its own lock, copy of
+// the phase and condvar, and a child touches its parent only when its own arrivals are
+// complete (or it gets its first party), so with many parties the arrivals and the
+// waiters are spread over small children instead of a single crowded lock. When the root
+// advances or terminates it walks down the tree, bringing every phaser to the new phase
+// and waking its waiters. Locks are taken from a child to its parent, never the other
+// way: the walk holds one lock at a time, and a child lets go of its own before arriving
+// at or registering with its parent.
+
+use std::fmt;
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, Weak};
+
+type OnAdvance = Box<dyn FnMut(u64, usize) -> bool + Send>;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Terminated;
+
+impl fmt::Display for Terminated {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "phaser terminated")
+    }
+}
+
+impl std::error::Error for Terminated {}
+
+struct State {
+    // in a child, behind the root's for as long as the walk takes to reach it
+    phase: u64,
+    parties: usize,
+    arrived: usize,
+    terminated: bool,
+    // a child is registering its first party with the parent, without holding the lock
+    joining: bool,
+    // the phasers created with this one as parent, to walk down at the end of a phase
+    children: Vec<Weak<Node>>,
+    // the root's only
+    on_advance: Option<OnAdvance>,
+}
+
+struct Node {
+    state: Mutex<State>,
+    cv: Condvar,
+}
+
+impl Node {
+    fn new(phase: u64, parties: usize) -> Arc<Node> {
+        let state = State {
+            phase,
+            parties,
+            arrived: 0,
+            terminated: false,
+            joining: false,
+            children: Vec::new(),
+            on_advance: None,
+        };
+        Arc::new(Node { state: Mutex::new(state), cv: Condvar::new() })
+    }
+}
+
+pub struct Phaser {
+    node: Arc<Node>,
+    parent: Option<Arc<Phaser>>,
+}
+
+impl Phaser {
+    pub fn new(parties: usize) -> Phaser {
+        Phaser { node: Node::new(0, parties), parent: None }
+    }
+
+    // a child of parent, registered with it as a single party if parties > 0
+    pub fn with_parent(parent: &Arc<Phaser>, parties: usize) -> Result<Phaser, Terminated> {
+        let node = {
+            let mut state = parent.node.state.lock().unwrap();
+            if state.terminated {
+                return Err(Terminated);
+            }
+            // from now on the walks of the parent reach the child too
+            let node = Node::new(state.phase, 0);
+            state.children.retain(|c| c.strong_count() > 0);
+            state.children.push(Arc::downgrade(&node));
+            node
+        };
+        let phaser = Phaser { node, parent: Some(parent.clone()) };
+        phaser.bulk_register(parties)?;
+        Ok(phaser)
+    }
+
+    // f(phase, registered) runs at the end of every phase, on the thread that arrived
+    // last, and returns true to terminate the phaser. Only for a root: the children follow
+    // its phases
+    pub fn on_advance<F: FnMut(u64, usize) -> bool + Send + 'static>(self, f: F) -> Phaser {
+        assert!(self.parent.is_none(), "Phaser: on_advance is decided by the root");
+        self.lock().on_advance = Some(Box::new(f));
+        self
+    }
+
+    pub fn parent(&self) -> Option<&Arc<Phaser>> {
+        self.parent.as_ref()
+    }
+
+    // the current phase, the same in the whole tree once the walk of the root is over
+    pub fn phase(&self) -> u64 {
+        self.lock().phase
+    }
+
+    pub fn is_terminated(&self) -> bool {
+        self.lock().terminated
+    }
+
+    pub fn registered_parties(&self) -> usize {
+        self.lock().parties
+    }
+
+    pub fn arrived_parties(&self) -> usize {
+        self.lock().arrived
+    }
+
+    pub fn unarrived_parties(&self) -> usize {
+        let state = self.lock();
+        state.parties - state.arrived
+    }
+
+    // a new party, which takes part in the current phase; returns the phase
+    pub fn register(&self) -> Result<u64, Terminated> {
+        self.bulk_register(1)
+    }
+
+    pub fn bulk_register(&self, parties: usize) -> Result<u64, Terminated> {
+        loop {
+            let mut state = self.lock();
+            while state.joining {
+                state = self.node.cv.wait(state).unwrap();
+            }
+            if state.terminated {
+                return Err(Terminated);
+            }
+            // a child whose parties have all arrived has already told its parent: the new
+            // party belongs to the next phase
+            if self.parent.is_some() && state.parties > 0 && state.arrived == state.parties {
+                let phase = state.phase;
+                drop(state);
+                self.await_advance(phase)?;
+                continue;
+            }
+            let parent = match &self.parent {
+                Some(parent) if state.parties == 0 && parties > 0 => parent,
+                _ => {
+                    state.parties += parties;
+                    return Ok(state.phase);
+                }
+            };
+            // the parent may have to wait for its next phase to take the child: the others
+            // registering here wait for it on joining, the rest go on
+            state.joining = true;
+            drop(state);
+            let joined = parent.bulk_register(1);
+            let mut state = self.lock();
+            state.joining = false;
+            self.node.cv.notify_all();
+            let phase = joined?;
+            if state.phase < phase {
+                state.phase = phase;
+                state.arrived = 0;
+            }
+            state.parties += parties;
+            return Ok(state.phase);
+        }
+    }
+
+    // arrive without waiting for the others; returns the phase arrived at.
+    // panics if all the registered parties have already arrived
+    pub fn arrive(&self) -> Result<u64, Terminated> {
+        self.arrive_as(false)
+    }
+
+    // arrive and leave: the phaser has one party less from now on
+    pub fn arrive_and_deregister(&self) -> Result<u64, Terminated> {
+        self.arrive_as(true)
+    }
+
+    // arrive and wait for the others; returns the new phase, or Err if the phaser
+    // terminated at the end of this one
+    pub fn arrive_and_await_advance(&self) -> Result<u64, Terminated> {
+        let phase = self.arrive()?;
+        self.await_advance(phase)
+    }
+
+    // wait until the phaser is past phase, returning the current phase; at once if it
+    // already is
+    pub fn await_advance(&self, phase: u64) -> Result<u64, Terminated> {
+        let mut state = self.lock();
+        while state.phase == phase && !state.terminated {
+            state = self.node.cv.wait(state).unwrap();
+        }
+        if state.terminated {
+            Err(Terminated)
+        } else {
+            Ok(state.phase)
+        }
+    }
+
+    // terminate now, releasing all the waiters
+    pub fn force_termination(&self) {
+        let root = self.root();
+        let (phase, children) = {
+            let mut state = root.lock();
+            state.terminated = true;
+            (state.phase, state.children.clone())
+        };
+        root.node.cv.notify_all();
+        release(children, phase, true);
+    }
+
+    fn lock(&self) -> MutexGuard<'_, State> {
+        self.node.state.lock().unwrap()
+    }
+
+    fn root(&self) -> &Phaser {
+        let mut p = self;
+        while let Some(parent) = &p.parent {
+            p = parent;
+        }
+        p
+    }
+
+    fn arrive_as(&self, deregister: bool) -> Result<u64, Terminated> {
+        let mut state = self.lock();
+        if state.arrived == state.parties && self.parent.is_some() {
+            // a party quicker than the walk: the root may be in the next phase already
+            let root = self.root().lock();
+            if root.phase > state.phase {
+                state.phase = root.phase;
+                state.arrived = 0;
+            }
+            state.terminated |= root.terminated;
+        }
+        if state.terminated {
+            return Err(Terminated);
+        }
+        assert!(state.arrived < state.parties, "Phaser: more arrivals than registered parties");
+        let phase = state.phase;
+        if deregister {
+            state.parties -= 1;
+        } else {
+            state.arrived += 1;
+        }
+        if state.arrived == state.parties {
+            self.advance(state);
+        }
+        Ok(phase)
+    }
+
+    // all the parties have arrived: a child arrives at its parent (and leaves it if it has
+    // no parties left), the root moves to the next phase and walks down the tree
+    fn advance(&self, mut state: MutexGuard<'_, State>) {
+        match &self.parent {
+            Some(parent) => {
+                let leaving = state.parties == 0;
+                drop(state);
+                // Err: the tree terminated in the meantime, and the waiters know
+                let _ = parent.arrive_as(leaving);
+            }
+            None => {
+                let (phase, parties) = (state.phase, state.parties);
+                let terminate = match &mut state.on_advance {
+                    Some(f) => f(phase, parties),
+                    None => parties == 0,
+                };
+                state.phase += 1;
+                state.arrived = 0;
+                state.terminated |= terminate;
+                let (phase, terminated, children) = (state.phase, state.terminated, state.children.clone());
+                drop(state);
+                self.node.cv.notify_all();
+                release(children, phase, terminated);
+            }
+        }
+    }
+}
+
+// brings the phasers below the root to its phase and termination and wakes their waiters
+fn release(mut nodes: Vec<Weak<Node>>, phase: u64, terminated: bool) {
+    while let Some(weak) = nodes.pop() {
+        let node = match weak.upgrade() {
+            Some(node) => node,
+            None => continue,
+        };
+        let mut state = node.state.lock().unwrap();
+        // a party quicker than the walk may have brought it there already
+        if state.phase < phase {
+            state.phase = phase;
+            state.arrived = 0;
+        }
+        state.terminated |= terminated;
+        nodes.extend(state.children.iter().cloned());
+        drop(state);
+        node.cv.notify_all();
+    }
+}
+
+impl fmt::Debug for Phaser {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let state = self.lock();
+        f.debug_struct("Phaser")
+            .field("phase", &state.phase)
+            .field("parties", &state.parties)
+            .field("arrived", &state.arrived)
+            .field("terminated", &state.terminated)
+            .field("tiered", &self.parent.is_some())
+            .finish()
+    }
+}
+
+#[cfg(all(test, not(loom)))]
+mod tests {
+    use super::*;
+    use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+    use std::thread;
+
+    const PHASES: usize = 20;
+
+    // every thread does some work in each phase and waits for the others: nobody may see
+    // the work of a phase incomplete once it has advanced past it
+    fn phases_in_lockstep(phasers: &[Arc<Phaser>], per_phaser: usize) {
+        let threads = phasers.len() * per_phaser;
+        let done: Vec<AtomicUsize> = (0..PHASES).map(|_| AtomicUsize::new(0)).collect();
+        thread::scope(|s| {
+            for phaser in phasers {
+                for _ in 0..per_phaser {
+                    let done = &done;
+                    s.spawn(move || {
+                        for (p, work) in done.iter().enumerate() {
+                            assert_eq!(phaser.phase(), p as u64);
+                            work.fetch_add(1, Ordering::Relaxed);
+                            assert_eq!(phaser.arrive_and_await_advance(), Ok(p as u64 + 1));
+                            assert_eq!(work.load(Ordering::Relaxed), threads);
+                        }
+                    });
+                }
+            }
+        });
+    }
+
+    #[test]
+    fn phases_advance_together() {
+        phases_in_lockstep(&[Arc::new(Phaser::new(4))], 4);
+    }
+
+    #[test]
+    fn tiered_phasers() {
+        let root = Arc::new(Phaser::new(0));
+        let children: Vec<_> = (0..4).map(|_| Arc::new(Phaser::with_parent(&root, 4).unwrap())).collect();
+        assert_eq!(root.registered_parties(), 4);
+        phases_in_lockstep(&children, 4);
+        assert_eq!(root.phase(), PHASES as u64);
+        // a tree of three levels
+        let root = Arc::new(Phaser::new(0));
+        let mid = Arc::new(Phaser::with_parent(&root, 0).unwrap());
+        let leaves: Vec<_> = (0..2).map(|_| Arc::new(Phaser::with_parent(&mid, 2).unwrap())).collect();
+        assert_eq!((root.registered_parties(), mid.registered_parties()), (1, 2));
+        phases_in_lockstep(&leaves, 2);
+    }
+
+    #[test]
+    fn arrive_does_not_wait() {
+        let phaser = Phaser::new(3);
+        assert_eq!(phaser.arrive(), Ok(0));
+        assert_eq!(phaser.arrive(), Ok(0));
+        assert_eq!((phaser.phase(), phaser.arrived_parties(), phaser.unarrived_parties()), (0, 2, 1));
+        assert_eq!(phaser.arrive(), Ok(0));
+        assert_eq!((phaser.phase(), phaser.arrived_parties()), (1, 0));
+        // the phase is over already
+        assert_eq!(phaser.await_advance(0), Ok(1));
+    }
+
+    #[test]
+    fn parties_join_and_leave() {
+        let phaser = Arc::new(Phaser::new(1));
+        thread::scope(|s| {
+            for i in 0..3 {
+                let phaser = phaser.clone();
+                // each worker joins in the current phase and leaves after i + 1 phases
+                phaser.register().unwrap();
+                s.spawn(move || {
+                    for _ in 0..i {
+                        phaser.arrive_and_await_advance().unwrap();
+                    }
+                    phaser.arrive_and_deregister().unwrap();
+                });
+            }
+            // the main thread goes through the same phases as the longest worker; worker i
+            // is gone by the end of phase i
+            for p in 0..3 {
+                assert_eq!(phaser.arrive_and_await_advance(), Ok(p as u64 + 1));
+                assert!(phaser.registered_parties() <= 3 - p);
+            }
+        });
+        assert_eq!(phaser.registered_parties(), 1);
+        assert!(!phaser.is_terminated());
+        // the last party leaving terminates it
+        assert_eq!(phaser.arrive_and_deregister(), Ok(3));
+        assert!(phaser.is_terminated());
+        assert_eq!(phaser.register(), Err(Terminated));
+        assert_eq!(phaser.arrive(), Err(Terminated));
+    }
+
+    #[test]
+    fn on_advance_terminates() {
+        let phaser = Arc::new(Phaser::new(2).on_advance(|phase, _| phase == 2));
+        let results: Vec<Vec<_>> = thread::scope(|s| {
+            let handles: Vec<_> = (0..2)
+                .map(|_| s.spawn(|| (0..4).map(|_| phaser.arrive_and_await_advance()).collect()))
+                .collect();
+            handles.into_iter().map(|h| h.join().unwrap()).collect()
+        });
+        for r in results {
+            assert_eq!(r, [Ok(1), Ok(2), Err(Terminated), Err(Terminated)]);
+        }
+    }
+
+    #[test]
+    fn force_termination_releases_the_waiters() {
+        let phaser = Arc::new(Phaser::new(2));
+        let child = Arc::new(Phaser::with_parent(&phaser, 1).unwrap());
+        thread::scope(|s| {
+            let waiter = s.spawn(|| child.arrive_and_await_advance());
+            while phaser.arrived_parties() == 0 {
+                thread::yield_now();
+            }
+            child.force_termination();
+            assert_eq!(waiter.join().unwrap(), Err(Terminated));
+        });
+        assert!(phaser.is_terminated() && child.is_terminated());
+    }
+
+    #[test]
+    fn a_child_joins_its_parent_when_it_gets_parties() {
+        let root = Arc::new(Phaser::new(1));
+        let child = Phaser::with_parent(&root, 0).unwrap();
+        assert_eq!(root.registered_parties(), 1);
+        child.register().unwrap();
+        assert_eq!(root.registered_parties(), 2);
+        child.arrive_and_deregister().unwrap();
+        assert_eq!(root.registered_parties(), 1);
+        assert_eq!(root.arrive(), Ok(0));
+        assert_eq!(root.phase(), 1);
+    }
+
+    #[test]
+    fn registering_through_a_parent_waits_for_its_phase() {
+        let root = Arc::new(Phaser::new(1));
+        let mid = Arc::new(Phaser::with_parent(&root, 1).unwrap());
+        let leaf = Phaser::with_parent(&mid, 0).unwrap();
+        // mid is done with phase 0: the party registering through it belongs to phase 1
+        assert_eq!(mid.arrive(), Ok(0));
+        let registered = AtomicBool::new(false);
+        thread::scope(|s| {
+            let joiner = s.spawn(|| {
+                let phase = leaf.register().unwrap();
+                registered.store(true, Ordering::SeqCst);
+                (phase, leaf.arrive().unwrap())
+            });
+            while !leaf.lock().joining {
+                thread::yield_now();
+            }
+            // the joiner waits for mid without holding the lock of leaf
+            assert_eq!(leaf.registered_parties(), 0);
+            assert!(!registered.load(Ordering::SeqCst));
+            assert_eq!(root.arrive(), Ok(0));
+            assert_eq!(joiner.join().unwrap(), (1, 1));
+        });
+        // phase 1 waits for the root and mid
+        assert_eq!((root.phase(), leaf.arrived_parties()), (1, 1));
+        assert_eq!(mid.arrive(), Ok(1));
+        assert_eq!(root.arrive_and_await_advance(), Ok(2));
+    }
+
+    #[test]
+    #[should_panic(expected = "more arrivals")]
+    fn too_many_arrivals() {
+        let phaser = Phaser::new(2);
+        let child = Phaser::with_parent(&Arc::new(phaser), 1).unwrap();
+        child.arrive().unwrap();
+        child.arrive().unwrap();
+    }
+}
@@ -0,0 +1,96 @@
+// RUSTFLAGS="--cfg loom" cargo test --release --test loom
+//
+// Loom runs the tests below under every interleaving of the threads (within the
+// preemption bound), checking that the rounds never mix: every waiter leaves with the
+// items of a single round, all the parties of the round get the same items and exactly
+// one of them is the leader.
+
+#![cfg(loom)]
+
+use es3::cb::{BarrierBroken, CyclicBarrier};
+use loom::sync::Arc;
+use loom::thread;
+
+// run `threads` threads doing `rounds` waits each, with item (thread, round); returns the
+// results of every thread
+fn run(parties: usize, threads: usize, rounds: usize) -> Vec<Vec<(Vec<(usize, usize)>, bool)>> {
+    let barrier = Arc::new(CyclicBarrier::new(parties));
+    let handles: Vec<_> = (0..threads)
+        .map(|t| {
+            let barrier = barrier.clone();
+            thread::spawn(move || {
+                (0..rounds)
+                    .map(|r| {
+                        let res = barrier.wait((t, r)).unwrap();
+                        (res.items, res.is_leader)
+                    })
+                    .collect::<Vec<_>>()
+            })
+        })
+        .collect();
+    handles.into_iter().map(|h| h.join().unwrap()).collect()
+}
+
+fn model<F: Fn() + Sync + Send + 'static>(f: F) {
+    let mut builder = loom::model::Builder::new();
+    builder.preemption_bound = Some(3);
+    builder.check(f);
+}
+
+#[test]
+fn rounds_do_not_mix() {
+    model(|| {
+        let results = run(2, 2, 3);
+        for r in 0..3 {
+            let (items, _) = &results[0][r];
+            // both the items of round r, and the same ones for both
+            let mut rounds: Vec<usize> = items.iter().map(|&(_, round)| round).collect();
+            rounds.dedup();
+            assert_eq!(rounds, [r]);
+            assert_eq!(items.len(), 2);
+            assert_eq!(results[1][r].0, *items);
+            assert!(results[0][r].1 != results[1][r].1, "one leader per round");
+        }
+    });
+}
+
+#[test]
+fn more_threads_than_parties() {
+    // 4 threads, 2 parties: two rounds of 2 in any combination, and a slow waiter of the
+    // first round may wake after the second is over
+    model(|| {
+        let results = run(2, 4, 1);
+        let mut seen: Vec<(usize, usize)> = Vec::new();
+        for (t, res) in results.iter().enumerate() {
+            let (items, _) = &res[0];
+            assert_eq!(items.len(), 2);
+            assert!(items.contains(&(t, 0)));
+            seen.extend(items);
+        }
+        seen.sort();
+        seen.dedup();
+        assert_eq!(seen.len(), 4);
+        let leaders = results.iter().filter(|r| r[0].1).count();
+        assert_eq!(leaders, 2);
+    });
+}
+
+#[test]
+fn reset_releases_the_waiter() {
+    // the waiter is either broken by reset, or arrives after it and completes the round
+    // with the main thread
+    model(|| {
+        let barrier = Arc::new(CyclicBarrier::new(2));
+        let waiter = {
+            let barrier = barrier.clone();
+            thread::spawn(move || barrier.wait(1))
+        };
+        barrier.reset();
+        let main = if barrier.generation() == 0 { Some(barrier.wait(0)) } else { None };
+        match (waiter.join().unwrap(), main) {
+            (Err(BarrierBroken::Broken), None) => {}
+            (Ok(a), Some(Ok(b))) => assert_eq!(a.items, b.items),
+            other => panic!("unexpected outcome {:?}", other),
+        }
+    });
+}
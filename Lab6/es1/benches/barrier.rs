@@ -0,0 +1,46 @@
+// cargo bench --bench barrier
+//
+// The topologies of the channel barrier against each other: n threads going through the
+// barrier for a number of rounds, reporting the time of a round. All to all sends
+// n(n-1) messages per round, so its rounds are shorter for it, to keep the run short.
+
+use std::thread;
+use std::time::{Duration, Instant};
+
+use es1::cb::{CyclicBarrier, Topology};
+
+// the time of a round of n threads, with rounds rounds
+fn time(n: usize, topology: Topology, rounds: usize) -> Duration {
+    let mut barrier = CyclicBarrier::<()>::with_topology(n, topology);
+    let waiters: Vec<_> = (0..n).map(|_| barrier.get_waiter()).collect();
+    let start = Instant::now();
+    thread::scope(|s| {
+        for mut w in waiters {
+            s.spawn(move || {
+                for _ in 0..rounds {
+                    w.wait().unwrap();
+                }
+            });
+        }
+    });
+    start.elapsed() / rounds as u32
+}
+
+fn main() {
+    print!("{:>7}", "threads");
+    for topology in Topology::ALL {
+        print!(" {:>14}", format!("{:?}", topology));
+    }
+    println!();
+    for n in (1..=8).map(|k| 1 << k) {
+        print!("{:>7}", n);
+        for topology in Topology::ALL {
+            let rounds = match topology {
+                Topology::AllToAll => (20_000 / (n * n)).max(5),
+                _ => (20_000 / n).max(20),
+            };
+            print!(" {:>14.2?}", time(n, topology, rounds));
+        }
+        println!();
+    }
+}
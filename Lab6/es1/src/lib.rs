@@ -1,28 +1,268 @@
 pub mod cb {
+    // A cyclic barrier made of channels: every waiter has its own receiver and senders to
+    // some of the others, and wait() is a round of messages among them. Who talks to whom
+    // is the Topology of the barrier, chosen at construction:
+    // - AllToAll: everyone sends to everyone, n(n-1) messages per round;
+    // - Central: everyone reports to waiter 0, which releases them all, 2(n-1) messages;
+    // - Dissemination: in step k waiter i sends to i + 2^k (mod n) and hears from
+    //   i - 2^k, log2(n) steps of n messages, with no waiter doing more than the others;
+    // - Tree: a binary tree rooted at waiter 0: the arrivals go up to the root and the
+    //   release comes down, 2(n-1) messages in 2 log2(n) steps.
+    // The items given to wait travel with the messages, so every waiter leaves with all of
+    // them whatever the topology.
+    //
+    // The messages carry the round and the step they belong to: a fast waiter may already
+    // be in the next round, and its message must not count for the current one, so it's
+    // kept aside until then. It can be at most one round ahead: it needs the others to
+    // finish the round it left.
+    //
+    // A waiter that is dropped tells the ones it sends to: when a waiter needs a message
+    // from one that has left, it fails with BarrierError::Disconnected and tells its own
+    // peers that the barrier is broken, so the error reaches every waiter whatever the
+    // topology. Waiters never handed out by get_waiter are dropped with the CyclicBarrier.
+
+    use std::fmt;
     use std::sync::mpsc::{channel, Receiver, Sender};
 
-    pub struct CyclicBarrier {
-        waiters: Vec<Waiter>,
-    } 
+    #[derive(Debug, Clone, Copy, PartialEq, Eq)]
+    pub enum BarrierError {
+        // the waiter with this index was dropped before the round was over
+        Disconnected(usize),
+    }
+
+    impl fmt::Display for BarrierError {
+        fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+            match self {
+                BarrierError::Disconnected(i) => write!(f, "waiter {} disconnected", i),
+            }
+        }
+    }
+
+    impl std::error::Error for BarrierError {}
+
+    #[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+    pub enum Topology {
+        #[default]
+        AllToAll,
+        Central,
+        Dissemination,
+        Tree,
+    }
+
+    impl Topology {
+        pub const ALL: [Topology; 4] = [Topology::AllToAll, Topology::Central, Topology::Dissemination, Topology::Tree];
+
+        // the waiters i sends to, out of n
+        fn peers(self, i: usize, n: usize) -> Vec<usize> {
+            let mut peers = match self {
+                Topology::AllToAll => (0..n).collect(),
+                Topology::Central if i == 0 => (0..n).collect(),
+                Topology::Central => vec![0],
+                Topology::Dissemination => distances(n).map(|d| (i + d) % n).collect(),
+                Topology::Tree => {
+                    let parent = if i > 0 { Some((i - 1) / 2) } else { None };
+                    parent.into_iter().chain(children(i, n)).collect()
+                }
+            };
+            peers.sort();
+            peers.dedup();
+            peers.retain(|&j| j != i);
+            peers
+        }
+    }
+
+    // the distances of the steps of a dissemination barrier: 1, 2, 4, ... below n
+    fn distances(n: usize) -> impl Iterator<Item = usize> {
+        std::iter::successors(Some(1usize), |d| d.checked_mul(2)).take_while(move |&d| d < n)
+    }
+
+    fn children(i: usize, n: usize) -> impl Iterator<Item = usize> {
+        [2 * i + 1, 2 * i + 2].into_iter().filter(move |&c| c < n)
+    }
 
-    pub struct Waiter {
-        receiver: Receiver<()>,
-        senders: Vec<Sender<()>>,
+    enum Msg<T> {
+        // the items known to from, at a step of a round
+        Data { from: usize, round: u64, step: usize, items: Vec<(usize, T)> },
+        Left(usize),
+        // the barrier is broken from this round, because waiter left
+        Broken { left: usize, round: u64 },
     }
 
-    impl Waiter {
-        pub fn wait(&self) {
-            for tx in &self.senders {
-                tx.send(());
+    pub struct CyclicBarrier<T = ()> {
+        // the waiters not handed out yet, the first at the end
+        waiters: Vec<Waiter<T>>,
+    }
+
+    pub struct Waiter<T = ()> {
+        index: usize,
+        parties: usize,
+        topology: Topology,
+        receiver: Receiver<Msg<T>>,
+        // (index, sender) of the waiters this one sends to, by index
+        senders: Vec<(usize, Sender<Msg<T>>)>,
+        round: u64,
+        // messages of later steps or rounds, received while waiting for another one, and
+        // Broken ones of the next round
+        early: Vec<Msg<T>>,
+        left: Vec<bool>,
+        broken: Option<usize>,
+    }
+
+    impl<T: Clone> Waiter<T> {
+        pub fn index(&self) -> usize {
+            self.index
+        }
+
+        pub fn parties(&self) -> usize {
+            self.parties
+        }
+
+        pub fn topology(&self) -> Topology {
+            self.topology
+        }
+
+        // wait for the others, giving them item; returns the items of all the waiters of
+        // the round, by index
+        pub fn exchange(&mut self, item: T) -> Result<Vec<T>, BarrierError> {
+            if let Some(j) = self.broken {
+                return Err(BarrierError::Disconnected(j));
+            }
+            let mut known: Vec<Option<T>> = vec![None; self.parties];
+            known[self.index] = Some(item);
+            if let Err(BarrierError::Disconnected(j)) = self.round_of(&mut known) {
+                return Err(self.fail(j));
+            }
+            self.round += 1;
+            Ok(known.into_iter().map(|item| item.expect("an item from every waiter")).collect())
+        }
+
+        // the messages of a round, according to the topology
+        fn round_of(&mut self, known: &mut [Option<T>]) -> Result<(), BarrierError> {
+            let (i, n) = (self.index, self.parties);
+            match self.topology {
+                Topology::AllToAll => {
+                    for j in (0..n).filter(|&j| j != i) {
+                        self.send(j, 0, known);
+                    }
+                    for j in (0..n).filter(|&j| j != i) {
+                        self.recv(j, 0, known)?;
+                    }
+                }
+                Topology::Central if i == 0 => {
+                    for j in 1..n {
+                        self.recv(j, 0, known)?;
+                    }
+                    for j in 1..n {
+                        self.send(j, 1, known);
+                    }
+                }
+                Topology::Central => {
+                    self.send(0, 0, known);
+                    self.recv(0, 1, known)?;
+                }
+                Topology::Dissemination => {
+                    for (step, d) in distances(n).enumerate() {
+                        self.send((i + d) % n, step, known);
+                        self.recv((i + n - d) % n, step, known)?;
+                    }
+                }
+                Topology::Tree => {
+                    for c in children(i, n) {
+                        self.recv(c, 0, known)?;
+                    }
+                    if i > 0 {
+                        let parent = (i - 1) / 2;
+                        self.send(parent, 0, known);
+                        self.recv(parent, 1, known)?;
+                    }
+                    for c in children(i, n) {
+                        self.send(c, 1, known);
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        // send the items known so far to waiter j; if j is gone the message is lost, and
+        // the error comes to this waiter as a Left or Broken from someone else
+        fn send(&self, j: usize, step: usize, known: &[Option<T>]) {
+            let items = known.iter().enumerate().filter_map(|(k, item)| Some((k, item.clone()?))).collect();
+            let msg = Msg::Data { from: self.index, round: self.round, step, items };
+            let (_, tx) = &self.senders[self.senders.binary_search_by_key(&j, |(k, _)| *k).expect("a sender for every peer")];
+            let _ = tx.send(msg);
+        }
+
+        // wait for the message of waiter j at step, adding its items to known
+        fn recv(&mut self, j: usize, step: usize, known: &mut [Option<T>]) -> Result<(), BarrierError> {
+            let round = self.round;
+            let is_it = |msg: &Msg<T>| matches!(msg, Msg::Data { from, round: r, step: s, .. } if *from == j && *r == round && *s == step);
+            // a waiter that failed in the next round may have told this one before it got
+            // all its messages for this round: such a Broken waits in early for its round
+            let broken = self.early.iter().find_map(|msg| match msg {
+                Msg::Broken { left, round: r } if *r <= round => Some(*left),
+                _ => None,
+            });
+            if let Some(k) = broken {
+                return Err(BarrierError::Disconnected(k));
             }
-            for _ in 0..self.senders.len(){
-                self.receiver.recv();
+            let msg = match self.early.iter().position(is_it) {
+                Some(pos) => self.early.swap_remove(pos),
+                None => loop {
+                    if self.left[j] {
+                        return Err(BarrierError::Disconnected(j));
+                    }
+                    match self.receiver.recv() {
+                        Ok(msg) if is_it(&msg) => break msg,
+                        Ok(Msg::Broken { left, round: r }) if r <= round => return Err(BarrierError::Disconnected(left)),
+                        Ok(msg @ (Msg::Data { .. } | Msg::Broken { .. })) => self.early.push(msg),
+                        // a waiter leaving after its last round is fine, as long as nobody
+                        // needs it any more: the check above
+                        Ok(Msg::Left(k)) => self.left[k] = true,
+                        // the others say Left before dropping their senders, this can't happen
+                        Err(_) => unreachable!("all the senders dropped without leaving"),
+                    }
+                },
+            };
+            if let Msg::Data { items, .. } = msg {
+                for (k, item) in items {
+                    known[k].get_or_insert(item);
+                }
             }
+            Ok(())
+        }
+
+        // the barrier is broken because j left: tell the peers, which may be waiting for
+        // this waiter and not for j
+        fn fail(&mut self, j: usize) -> BarrierError {
+            self.broken = Some(j);
+            for (_, tx) in &self.senders {
+                let _ = tx.send(Msg::Broken { left: j, round: self.round });
+            }
+            BarrierError::Disconnected(j)
+        }
+    }
+
+    impl Waiter<()> {
+        pub fn wait(&mut self) -> Result<(), BarrierError> {
+            self.exchange(()).map(|_| ())
         }
     }
 
-    impl CyclicBarrier {
+    impl<T> Drop for Waiter<T> {
+        fn drop(&mut self) {
+            for (_, tx) in &self.senders {
+                let _ = tx.send(Msg::Left(self.index));
+            }
+        }
+    }
+
+    impl<T: Clone> CyclicBarrier<T> {
+        // all to all, as it has always been
         pub fn new(n_waiters: usize) -> Self {
+            Self::with_topology(n_waiters, Topology::AllToAll)
+        }
+
+        pub fn with_topology(n_waiters: usize, topology: Topology) -> Self {
             let mut senders = vec![];
             let mut recievers = vec![];
 
@@ -34,20 +274,123 @@ pub mod cb {
             }
             let mut waiters = vec![];
 
-            recievers = recievers.into_iter().rev().collect();
-
-            for i in 0..n_waiters {
-                waiters.push(Waiter{receiver: recievers.pop().unwrap(),
-                                    senders: senders.iter().enumerate().filter_map(|(j,s)| {if i==j {None} else {Some(s.clone())}}).collect()})
+            for (i, receiver) in recievers.into_iter().enumerate() {
+                waiters.push(Waiter {
+                    index: i,
+                    parties: n_waiters,
+                    topology,
+                    receiver,
+                    senders: topology.peers(i, n_waiters).into_iter().map(|j| (j, senders[j].clone())).collect(),
+                    round: 0,
+                    early: Vec::new(),
+                    left: vec![false; n_waiters],
+                    broken: None,
+                })
             }
+            // handed out from the first
+            waiters.reverse();
             Self {waiters}
         }
 
+        // the waiters still to hand out
+        pub fn remaining(&self) -> usize {
+            self.waiters.len()
+        }
+
+        // the next waiter, in index order, or None if they have all been handed out
+        pub fn try_get_waiter(&mut self) -> Option<Waiter<T>> {
+            self.waiters.pop()
+        }
+
+        // panics if all the waiters have been handed out: see try_get_waiter
+        pub fn get_waiter(&mut self) -> Waiter<T> {
+            self.try_get_waiter().expect("No more waiters in the barrier!")
+        }
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+        use std::thread;
+
+        #[test]
+        fn rounds_exchange_the_items() {
+            for topology in Topology::ALL {
+                for n in [1, 2, 3, 4, 7, 8] {
+                    let mut barrier = CyclicBarrier::with_topology(n, topology);
+                    let handles: Vec<_> = (0..n)
+                        .map(|_| {
+                            let mut w = barrier.get_waiter();
+                            thread::spawn(move || {
+                                for round in 0..100 {
+                                    let items = w.exchange((w.index(), round)).unwrap();
+                                    assert_eq!(items, (0..n).map(|i| (i, round)).collect::<Vec<_>>(), "{:?}", topology);
+                                }
+                            })
+                        })
+                        .collect();
+                    for h in handles {
+                        h.join().unwrap();
+                    }
+                }
+            }
+        }
+
+        #[test]
+        fn peers() {
+            assert_eq!(Topology::AllToAll.peers(1, 4), [0, 2, 3]);
+            assert_eq!(Topology::Central.peers(0, 4), [1, 2, 3]);
+            assert_eq!(Topology::Central.peers(2, 4), [0]);
+            assert_eq!(Topology::Dissemination.peers(6, 8), [0, 2, 7]);
+            assert_eq!(Topology::Tree.peers(1, 5), [0, 3, 4]);
+            assert_eq!(Topology::Tree.peers(2, 5), [0]);
+            assert!(Topology::ALL.iter().all(|t| t.peers(0, 1).is_empty()));
+        }
+
+        #[test]
+        fn waiters_run_out() {
+            let mut barrier = CyclicBarrier::<()>::new(2);
+            assert_eq!(barrier.try_get_waiter().map(|w| w.index()), Some(0));
+            assert_eq!(barrier.remaining(), 1);
+            let mut w = barrier.get_waiter();
+            assert!(barrier.try_get_waiter().is_none());
+            // waiter 0 was dropped right away
+            assert_eq!(w.wait(), Err(BarrierError::Disconnected(0)));
+            assert_eq!(w.wait(), Err(BarrierError::Disconnected(0)));
+        }
+
+        #[test]
+        fn a_dropped_waiter_breaks_the_next_round() {
+            for topology in Topology::ALL {
+                let mut barrier = CyclicBarrier::<()>::with_topology(5, topology);
+                let mut waiters: Vec<_> = (0..5).map(|_| barrier.get_waiter()).collect();
+                let last = waiters.remove(3);
+                let leaver = thread::spawn(move || {
+                    let mut last = last;
+                    // one round, then gone
+                    last.wait().unwrap();
+                });
+                let handles: Vec<_> = waiters
+                    .into_iter()
+                    .map(|mut w| thread::spawn(move || (w.wait(), w.wait(), w.wait())))
+                    .collect();
+                leaver.join().unwrap();
+                // the ones that don't hear from waiter 3 directly are told by the others
+                for h in handles {
+                    let (first, second, third) = h.join().unwrap();
+                    assert_eq!(first, Ok(()), "{:?}", topology);
+                    assert_eq!(second, Err(BarrierError::Disconnected(3)), "{:?}", topology);
+                    assert_eq!(third, Err(BarrierError::Disconnected(3)), "{:?}", topology);
+                }
+            }
+        }
 
-        pub fn get_waiter(&mut self) -> Waiter {
-            if let Some(waiter) = self.waiters.pop() {
-                waiter
-            }else{ panic!("No more waiters in the barrier!") }
+        #[test]
+        #[should_panic(expected = "No more waiters")]
+        fn get_waiter_panics_when_empty() {
+            let mut barrier = CyclicBarrier::<()>::new(1);
+            let _w = barrier.get_waiter();
+            barrier.get_waiter();
         }
     }
-}
\ No newline at end of file
+}
@@ -1,14 +1,14 @@
-mod lib;
+use es1::cb;
 
 fn main() {
-    let mut cbarrier = lib::cb::CyclicBarrier::new(3);
+    let mut cbarrier = cb::CyclicBarrier::new(3);
     let mut vt = Vec::new();
     for i in 0..3 {
-        let waiter = cbarrier.get_waiter();
+        let mut waiter = cbarrier.get_waiter();
         vt.push(std::thread::spawn(move || {
             for j in 0..10 {
-                waiter.wait();
-                println!("after barrier {} {}", i, j);
+                let items = waiter.exchange(i * 10 + j).expect("barrier broken");
+                println!("after barrier {} {}, items: {:?}", i, j, items);
             }
         }));
     }